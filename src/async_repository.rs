@@ -0,0 +1,1193 @@
+use crate::branch::{self, Branch};
+use crate::builder::Settings;
+use crate::clone::CloneOptions;
+use crate::commit::CommitBuilder;
+use crate::config::{self, ConfigEntry, ConfigScope};
+use crate::diff::{self, Diff, DiffSpec, DiffStat};
+use crate::error::GitError;
+use crate::fetch::{self, FetchOptions, RefUpdate};
+use crate::log::{self, Commit, LogOptions};
+use crate::merge::{self, MergeOptions, MergeOutcome};
+use crate::metadata::{self, ObjectFormat};
+use crate::progress::{ProgressParser, ProgressSink};
+use crate::pull::{self, PullOptions, PullOutcome};
+use crate::push::{self, PushOptions, PushResult};
+use crate::rebase::{self, RebaseOptions, RebaseOutcome, RebaseState};
+use crate::remote::{self, Remote, RemoteHead};
+use crate::reset::{self, ResetMode};
+use crate::runner::{self, CancellationToken, GitOutput, Interruption, Invocation};
+use crate::stash::{self, StashApplyOutcome, StashEntry, StashOptions};
+use crate::status::{self, Status};
+use crate::submodule::{self, Submodule, SubmoduleUpdateOptions};
+use crate::tag::{self, ListTagsOptions, Tag, TagOptions};
+use crate::types::{BranchName, GitUrl, Result, TagName};
+use crate::worktree::{self, Worktree, WorktreeOptions};
+use crate::{
+    parse_lines, parse_optional, parse_remotes, parse_trimmed, process_output, Repository,
+};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::future;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::time;
+
+///A local git repository whose methods run git without blocking the current thread
+///
+///Mirrors the methods of `Repository`. Dropping a returned future before it completes kills
+///the git process it spawned.
+///
+///Converting a `Repository` keeps any settings made with `RepositoryBuilder`, but always
+///spawns git directly rather than going through the repository's `GitRunner`.
+pub struct AsyncRepository {
+    location: PathBuf,
+    bare: bool,
+    settings: Settings,
+}
+
+impl From<Repository> for AsyncRepository {
+    fn from(repo: Repository) -> AsyncRepository {
+        AsyncRepository {
+            location: repo.location,
+            bare: repo.bare,
+            settings: repo.settings,
+        }
+    }
+}
+
+impl AsyncRepository {
+    ///Create an AsyncRepository struct from a pre-existing local git repository
+    pub fn new<P: AsRef<Path>>(p: P) -> AsyncRepository {
+        AsyncRepository {
+            location: PathBuf::from(p.as_ref()),
+            bare: false,
+            settings: Settings::default(),
+        }
+    }
+
+    ///Find the repository containing the given folder, which may be anywhere within its working
+    ///tree or git directory
+    pub async fn discover<P: AsRef<Path>>(p: P) -> Result<AsyncRepository> {
+        let mut repo = AsyncRepository::new(p);
+        repo.locate(None).await?;
+        Ok(repo)
+    }
+
+    ///Open the repository at the given folder, which must be the root of its working tree or its
+    ///git directory, failing with `GitError::NotARepository` otherwise
+    pub async fn open<P: AsRef<Path>>(p: P) -> Result<AsyncRepository> {
+        let mut repo = AsyncRepository::new(p);
+        let ceiling = fs::canonicalize(&repo.location)
+            .await
+            .ok()
+            .and_then(|path| path.parent().map(|parent| parent.to_owned()));
+        repo.locate(ceiling).await?;
+        Ok(repo)
+    }
+
+    ///Clone a remote git repository locally
+    pub async fn clone<P: AsRef<Path>>(url: GitUrl, p: P) -> Result<AsyncRepository> {
+        AsyncRepository::clone_with(&url, p, &CloneOptions::new()).await
+    }
+
+    ///Clone a remote git repository locally with the given options
+    pub async fn clone_with<P: AsRef<Path>>(
+        url: &GitUrl,
+        p: P,
+        options: &CloneOptions,
+    ) -> Result<AsyncRepository> {
+        let p = p.as_ref();
+        let mut repo = AsyncRepository::new(p);
+        repo.bare = options.is_bare();
+
+        let cwd = env::current_dir().map_err(|_| GitError::WorkingDirectoryInaccessible)?;
+        let invocation = repo.settings.invocation(cwd, options.args(url, p));
+        repo.run(invocation, |_| Ok(())).await?;
+        Ok(repo)
+    }
+
+    ///Clone a remote git repository locally, reporting progress as it runs
+    pub async fn clone_with_progress<P: AsRef<Path>, S: ProgressSink + Send>(
+        url: &GitUrl,
+        p: P,
+        options: &CloneOptions,
+        mut progress: S,
+    ) -> Result<AsyncRepository> {
+        let p = p.as_ref();
+        let mut repo = AsyncRepository::new(p);
+        repo.bare = options.is_bare();
+
+        let cwd = env::current_dir().map_err(|_| GitError::WorkingDirectoryInaccessible)?;
+        let mut args = options.args(url, p);
+        args.insert(1, "--progress".into());
+        let invocation = repo.settings.invocation(cwd, args);
+        repo.run_with_progress(invocation, &mut progress, |_| Ok(()))
+            .await?;
+        Ok(repo)
+    }
+
+    ///Initialise a given folder as a git repository
+    pub async fn init<P: AsRef<Path>>(p: P) -> Result<AsyncRepository> {
+        let repo = AsyncRepository::new(p);
+        repo.execute_git(["init"]).await?;
+        Ok(repo)
+    }
+
+    ///A handle to the same repository whose commands are killed if they run for longer than
+    ///the given duration, returning `GitError::Timeout`
+    pub fn with_timeout(&self, timeout: Duration) -> AsyncRepository {
+        let mut repo = self.handle();
+        repo.settings.timeout = Some(timeout);
+        repo
+    }
+
+    ///A handle to the same repository whose commands are killed once the given token is
+    ///cancelled, returning `GitError::Cancelled`
+    pub fn with_cancellation(&self, token: &CancellationToken) -> AsyncRepository {
+        let mut repo = self.handle();
+        repo.settings.cancellation = Some(token.clone());
+        repo
+    }
+
+    fn handle(&self) -> AsyncRepository {
+        AsyncRepository {
+            location: self.location.clone(),
+            bare: self.bare,
+            settings: self.settings.clone(),
+        }
+    }
+
+    ///Returns true if this repository has no working tree
+    pub fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    ///The root of the working tree, `None` for a bare repository or a handle to a git directory
+    pub async fn workdir(&self) -> Result<Option<PathBuf>> {
+        let discovery = self
+            .execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)
+            .await?;
+        if !discovery.inside_work_tree {
+            return Ok(None);
+        }
+        let toplevel = self
+            .execute_git_fn(metadata::TOPLEVEL_ARGS, parse_trimmed)
+            .await?;
+        Ok(Some(PathBuf::from(toplevel)))
+    }
+
+    ///The absolute path of the git directory, which is within the main repository's for a
+    ///linked worktree
+    pub async fn git_dir(&self) -> Result<PathBuf> {
+        let discovery = self
+            .execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)
+            .await?;
+        Ok(discovery.git_dir)
+    }
+
+    ///Returns true if the repository was cloned with truncated history
+    pub async fn is_shallow(&self) -> Result<bool> {
+        self.execute_git_fn(metadata::SHALLOW_ARGS, metadata::parse_bool)
+            .await
+    }
+
+    ///Returns true if this is a linked worktree rather than the main working tree
+    pub async fn is_worktree(&self) -> Result<bool> {
+        let discovery = self
+            .execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)
+            .await?;
+        Ok(discovery.git_dir != discovery.common_dir)
+    }
+
+    ///The hash algorithm used to name objects
+    pub async fn object_format(&self) -> Result<ObjectFormat> {
+        self.execute_git_fn(metadata::OBJECT_FORMAT_ARGS, metadata::parse_object_format)
+            .await
+    }
+
+    ///Create and checkout a new local branch
+    pub async fn create_local_branch(&self, branch_name: &BranchName) -> Result<()> {
+        self.execute_git(["checkout", "-b", branch_name.value.as_str()])
+            .await
+    }
+
+    ///Checkout the specified branch
+    pub async fn switch_branch(&self, branch_name: &BranchName) -> Result<()> {
+        self.execute_git(["checkout", branch_name.value.as_str()])
+            .await
+    }
+
+    ///Add file contents to the index
+    pub async fn add(&self, pathspecs: Vec<&str>) -> Result<()> {
+        let mut args = pathspecs.clone();
+        args.insert(0, "add");
+        self.execute_git(args).await
+    }
+
+    ///Remove file contents from the index
+    pub async fn remove(&self, pathspecs: Vec<&str>, force: bool) -> Result<()> {
+        let mut args = pathspecs.clone();
+        args.insert(0, "rm");
+        if force {
+            args.push("-f");
+        }
+        self.execute_git(args).await
+    }
+
+    ///Move the current branch to the target commit, updating the index and working tree as
+    ///the mode requires
+    pub async fn reset(&self, mode: ResetMode, target: &str) -> Result<()> {
+        self.execute_git(reset::reset_args(mode, target)).await
+    }
+
+    ///Restore paths in the working tree, the index or both from `source`, or from the index if
+    ///not given
+    ///
+    ///Only the working tree is restored when neither `staged` nor `worktree` is given.
+    pub async fn restore(
+        &self,
+        pathspecs: Vec<&str>,
+        source: Option<&str>,
+        staged: bool,
+        worktree: bool,
+    ) -> Result<()> {
+        self.execute_git(reset::restore_args(pathspecs, source, staged, worktree))
+            .await
+    }
+
+    ///Replace paths in the index and working tree with their contents in `tree_ish`, or in
+    ///the index if not given
+    pub async fn checkout_paths(&self, tree_ish: Option<&str>, pathspecs: Vec<&str>) -> Result<()> {
+        self.execute_git(reset::checkout_paths_args(tree_ish, pathspecs))
+            .await
+    }
+
+    ///Discard all changes to tracked files and remove untracked files, keeping ignored ones
+    ///
+    ///On a branch with no commits yet, staged files are removed too. Untracked directories
+    ///containing a git repository, such as a nested clone, are left in place.
+    pub async fn discard_all_changes(&self) -> Result<()> {
+        self.execute_git(reset::HARD_RESET_ARGS).await?;
+        self.execute_git(reset::CLEAN_ARGS).await
+    }
+
+    ///Commit all staged files
+    pub async fn commit_all(&self, message: &str) -> Result<()> {
+        self.commit(&CommitBuilder::new(message).all())
+            .await
+            .map(|_| ())
+    }
+
+    ///Create a new commit, returning its hash
+    pub async fn commit(&self, builder: &CommitBuilder) -> Result<String> {
+        let mut invocation = self.invocation(builder.args());
+        invocation.envs.extend(builder.envs());
+        invocation.input = builder.input();
+        self.run(invocation, |_| Ok(())).await?;
+        self.get_hash(false).await
+    }
+
+    ///Push the curent branch to its associated remote
+    pub async fn push(&self) -> Result<()> {
+        self.execute_git(["push"]).await
+    }
+
+    ///Push the curent branch to its associated remote, reporting progress as it runs
+    pub async fn push_with_progress<S: ProgressSink + Send>(&self, mut progress: S) -> Result<()> {
+        let invocation = self.invocation(["push", "--progress"]);
+        self.run_with_progress(invocation, &mut progress, |_| Ok(()))
+            .await
+    }
+
+    ///Push the curent branch to its associated remote, specifying the upstream branch
+    pub async fn push_to_upstream(
+        &self,
+        upstream: &str,
+        upstream_branch: &BranchName,
+    ) -> Result<()> {
+        self.execute_git(["push", "-u", upstream, upstream_branch.value.as_str()])
+            .await
+    }
+
+    ///Push to a remote with the given options, returning the outcome for each ref
+    ///
+    ///Refs the push was refused for are returned with their reason rather than as an error.
+    pub async fn push_with(&self, options: &PushOptions) -> Result<Vec<PushResult>> {
+        match self
+            .execute_git_fn(options.args(), push::parse_results)
+            .await
+        {
+            Err(error) => push::rejected_results(error),
+            result => result,
+        }
+    }
+
+    ///Add a new remote
+    pub async fn add_remote(&self, name: &str, url: &GitUrl) -> Result<()> {
+        self.execute_git(["remote", "add", name, url.value.as_str()])
+            .await
+    }
+
+    ///Fetch a remote
+    pub async fn fetch_remote(&self, remote: &str) -> Result<()> {
+        self.execute_git(["fetch", remote]).await
+    }
+
+    ///Fetch a remote, reporting progress as it runs
+    pub async fn fetch_remote_with_progress<S: ProgressSink + Send>(
+        &self,
+        remote: &str,
+        mut progress: S,
+    ) -> Result<()> {
+        let invocation = self.invocation(["fetch", "--progress", remote]);
+        self.run_with_progress(invocation, &mut progress, |_| Ok(()))
+            .await
+    }
+
+    ///Fetch from a remote, returning the local refs which were updated
+    ///
+    ///Refs which couldn't be updated are returned flagged as rejected rather than as an error.
+    ///Requires git 2.41 or later, which added `fetch --porcelain`.
+    pub async fn fetch(&self, options: &FetchOptions) -> Result<Vec<RefUpdate>> {
+        match self
+            .execute_git_fn(options.args(), fetch::parse_updates)
+            .await
+        {
+            Err(error) => fetch::rejected_updates(error),
+            result => result,
+        }
+    }
+
+    ///Create a new branch from a start point, such as another local or remote branch
+    pub async fn create_branch_from_startpoint(
+        &self,
+        branch_name: &BranchName,
+        startpoint: &str,
+    ) -> Result<()> {
+        self.execute_git([
+            "checkout",
+            "-b",
+            branch_name.to_string().as_str(),
+            startpoint,
+        ])
+        .await
+    }
+
+    ///List local branches
+    pub async fn list_branches(&self) -> Result<Vec<String>> {
+        self.execute_git_fn(["branch", "--format=%(refname:short)"], parse_lines)
+            .await
+    }
+
+    ///Rename a local branch
+    pub async fn rename_branch(&self, from: &BranchName, to: &BranchName) -> Result<()> {
+        self.execute_git(["branch", "--move", from.value.as_str(), to.value.as_str()])
+            .await
+    }
+
+    ///Delete a local branch, which unless forced must be merged into its upstream or `HEAD`
+    pub async fn delete_branch(&self, branch_name: &BranchName, force: bool) -> Result<()> {
+        let mut args = vec!["branch", "--delete"];
+        if force {
+            args.push("--force");
+        }
+        args.push(branch_name.value.as_str());
+        self.execute_git(args).await
+    }
+
+    ///Set the upstream of a local branch, such as `origin/main`
+    pub async fn set_upstream(&self, branch_name: &BranchName, upstream: &str) -> Result<()> {
+        self.execute_git([
+            "branch",
+            format!("--set-upstream-to={}", upstream).as_str(),
+            branch_name.value.as_str(),
+        ])
+        .await
+    }
+
+    ///Remove the upstream of a local branch
+    pub async fn unset_upstream(&self, branch_name: &BranchName) -> Result<()> {
+        self.execute_git(["branch", "--unset-upstream", branch_name.value.as_str()])
+            .await
+    }
+
+    ///The name of the checked out branch, `None` if `HEAD` is detached
+    pub async fn current_branch(&self) -> Result<Option<String>> {
+        self.execute_git_fn(["branch", "--show-current"], parse_optional)
+            .await
+    }
+
+    ///List local and remote-tracking branches along with their upstreams and tips
+    pub async fn list_branches_detailed(&self) -> Result<Vec<Branch>> {
+        self.execute_git_fn(branch::list_args(), branch::parse_branches)
+            .await
+    }
+
+    ///Get the status of the working tree and index
+    pub async fn status(&self) -> Result<Status> {
+        self.execute_git_fn(status::STATUS_ARGS, status::parse_status)
+            .await
+    }
+
+    ///Get the status of the working tree and index, including ignored files
+    pub async fn status_with_ignored(&self) -> Result<Status> {
+        self.execute_git_fn(status::IGNORED_STATUS_ARGS, status::parse_status)
+            .await
+    }
+
+    ///List files added to staging area
+    pub async fn list_added(&self) -> Result<Vec<String>> {
+        Ok(self.status().await?.added())
+    }
+
+    ///List all modified files, whether or not the modifications are staged
+    pub async fn list_modified(&self) -> Result<Vec<String>> {
+        Ok(self.status().await?.modified())
+    }
+
+    ///List all untracked files
+    pub async fn list_untracked(&self) -> Result<Vec<String>> {
+        Ok(self.status().await?.untracked)
+    }
+
+    ///List tracked files
+    pub async fn list_tracked(&self) -> Result<Vec<String>> {
+        self.execute_git_fn(["ls-files"], parse_lines).await
+    }
+
+    ///List all the remote URI for name
+    pub async fn show_remote_uri(&self, remote_name: &str) -> Result<String> {
+        self.execute_git_fn(
+            [
+                "config",
+                "--get",
+                format!("remote.{}.url", remote_name).as_str(),
+            ],
+            parse_trimmed,
+        )
+        .await
+    }
+
+    ///The value of a config key, `None` if it isn't set
+    ///
+    ///Without a scope the value that applies to this repository is returned, which for a key
+    ///set several times is the last.
+    pub async fn config_get(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Option<String>> {
+        Ok(self
+            .config_values(config::get_args(scope, None, false, key))
+            .await?
+            .pop())
+    }
+
+    ///Every value of a multi-valued config key, in the order git reads them
+    pub async fn config_get_all(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Vec<String>> {
+        self.config_values(config::get_args(scope, None, true, key))
+            .await
+    }
+
+    ///The value of a config key interpreted by git as a boolean, such as `yes` or `off`
+    pub async fn config_get_bool(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Option<bool>> {
+        self.config_values(config::get_args(scope, Some("bool"), false, key))
+            .await?
+            .pop()
+            .map(|value| config::parse_bool(&value))
+            .transpose()
+    }
+
+    ///The value of a config key interpreted by git as an integer, expanding suffixes such as `k`
+    pub async fn config_get_int(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Option<i64>> {
+        self.config_values(config::get_args(scope, Some("int"), false, key))
+            .await?
+            .pop()
+            .map(|value| config::parse_int(&value))
+            .transpose()
+    }
+
+    ///The value of a config key interpreted by git as a path, expanding a leading `~`
+    pub async fn config_get_path(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Option<PathBuf>> {
+        Ok(self
+            .config_values(config::get_args(scope, Some("path"), false, key))
+            .await?
+            .pop()
+            .map(PathBuf::from))
+    }
+
+    ///Set a config key, replacing any values it already has in the given scope
+    pub async fn config_set(&self, key: &str, value: &str, scope: &ConfigScope) -> Result<()> {
+        self.execute_git(config::set_args(scope, key, value, false))
+            .await
+    }
+
+    ///Add a value to a multi-valued config key, keeping the values it already has
+    pub async fn config_add(&self, key: &str, value: &str, scope: &ConfigScope) -> Result<()> {
+        self.execute_git(config::set_args(scope, key, value, true))
+            .await
+    }
+
+    ///Remove every value of a config key in the given scope, succeeding if it isn't set
+    pub async fn config_unset(&self, key: &str, scope: &ConfigScope) -> Result<()> {
+        match self.execute_git(config::unset_args(scope, key)).await {
+            Err(error) if error.exit_code() == Some(config::NOTHING_TO_UNSET_EXIT_CODE) => Ok(()),
+            result => result,
+        }
+    }
+
+    ///List config values with where each was read from, across all scopes unless one is given
+    pub async fn config_list(&self, scope: Option<&ConfigScope>) -> Result<Vec<ConfigEntry>> {
+        self.execute_git_fn(config::list_args(scope), config::parse_entries)
+            .await
+    }
+
+    ///List all the remote URI for name
+    pub async fn list_remotes(&self) -> Result<Vec<String>> {
+        self.execute_git_fn(["remote", "show"], parse_remotes).await
+    }
+
+    ///List remotes with their URLs and default refspecs, empty if there are none
+    pub async fn remotes(&self) -> Result<Vec<Remote>> {
+        let mut remotes = self
+            .execute_git_fn(remote::VERBOSE_LIST_ARGS, remote::parse_remotes)
+            .await?;
+        if !remotes.is_empty() {
+            let refspecs = self.config_values(remote::REFSPECS_ARGS).await?;
+            remote::apply_refspecs(&mut remotes, &refspecs)?;
+        }
+        Ok(remotes)
+    }
+
+    ///Rename a remote, along with its remote-tracking branches and configuration
+    pub async fn rename_remote(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.execute_git(remote::rename_args(old_name, new_name))
+            .await
+    }
+
+    ///Remove a remote, along with its remote-tracking branches and configuration
+    pub async fn remove_remote(&self, name: &str) -> Result<()> {
+        self.execute_git(remote::remove_args(name)).await
+    }
+
+    ///Replace the URL of a remote, or its push URL if `push` is given
+    pub async fn set_remote_url(&self, name: &str, url: &GitUrl, push: bool) -> Result<()> {
+        self.execute_git(remote::set_url_args(name, url.value.as_str(), push))
+            .await
+    }
+
+    ///Delete remote-tracking branches whose branch no longer exists on the remote, returning
+    ///their names
+    ///
+    ///With `dry_run` the branches which would be deleted are returned and none are.
+    pub async fn prune_remote(&self, name: &str, dry_run: bool) -> Result<Vec<String>> {
+        self.execute_git_fn(remote::prune_args(name, dry_run), remote::parse_pruned)
+            .await
+    }
+
+    ///Set which remote-tracking branch a remote's `HEAD` points at, such as `origin/HEAD`
+    pub async fn remote_set_head(&self, name: &str, head: &RemoteHead) -> Result<()> {
+        self.execute_git(remote::set_head_args(name, head)).await
+    }
+
+    /// Obtains commit hash of the current `HEAD`.
+    pub async fn get_hash(&self, short: bool) -> Result<String> {
+        let args: &[_] = if short {
+            &["rev-parse", "--short", "HEAD"]
+        } else {
+            &["rev-parse", "HEAD"]
+        };
+        self.execute_git_fn(args, parse_trimmed).await
+    }
+
+    ///Merge other branches or commits into the current branch
+    ///
+    ///Conflicts are reported as `MergeOutcome::Conflicted` rather than an error, leaving the
+    ///merge in progress to be resolved and completed with `merge_continue`, or abandoned with
+    ///`merge_abort`.
+    pub async fn merge(&self, options: &MergeOptions) -> Result<MergeOutcome> {
+        let before = self.get_hash(false).await.ok();
+        let targets = self
+            .execute_git_fn(options.target_args(), parse_lines)
+            .await?;
+
+        if let Err(error) = self.execute_git(options.args()).await {
+            let conflicts = self
+                .execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)
+                .await?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(MergeOutcome::Conflicted { conflicts })
+            };
+        }
+
+        if options.is_squash() {
+            let index = self.execute_git_fn(["write-tree"], parse_trimmed).await?;
+            let head = self
+                .execute_git_fn(["rev-parse", "HEAD^{tree}"], parse_trimmed)
+                .await?;
+            return Ok(if index == head {
+                MergeOutcome::UpToDate
+            } else {
+                MergeOutcome::Squashed
+            });
+        }
+        let after = self.get_hash(false).await?;
+        Ok(merge::outcome(before.as_deref(), &after, &targets))
+    }
+
+    ///Commit a merge once its conflicts have been resolved and staged, returning the hash of
+    ///the merge commit
+    pub async fn merge_continue(&self) -> Result<String> {
+        let mut invocation = self.invocation(["merge", "--continue"]);
+        invocation.envs.push(("GIT_EDITOR".into(), ":".into()));
+        self.run(invocation, |_| Ok(())).await?;
+        self.get_hash(false).await
+    }
+
+    ///Abandon a conflicted merge, restoring the state from before it started
+    pub async fn merge_abort(&self) -> Result<()> {
+        self.execute_git(["merge", "--abort"]).await
+    }
+
+    ///Fetch from a remote and integrate the fetched commits into the current branch
+    ///
+    ///Conflicts are reported as `PullOutcome::Conflicted` rather than an error, leaving the merge
+    ///or rebase in progress to be resolved and continued, or aborted.
+    pub async fn pull(&self, options: &PullOptions) -> Result<PullOutcome> {
+        let before = self.get_hash(false).await.ok();
+        if let Err(error) = self.execute_git(options.args()).await {
+            let conflicts = self
+                .execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)
+                .await?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(PullOutcome::Conflicted { conflicts })
+            };
+        }
+        let after = self.get_hash(false).await?;
+        let fetched = self
+            .execute_git_fn(pull::FETCHED_ARGS, parse_trimmed)
+            .await?;
+        Ok(pull::outcome(before, after, &fetched, options.is_rebase()))
+    }
+
+    ///Replay the commits of the current branch on top of another
+    ///
+    ///A rebase stopped by conflicts or a failing `exec` command returns
+    ///`RebaseOutcome::Stopped`, to be resumed with `rebase_continue` or `rebase_skip`, or
+    ///abandoned with `rebase_abort`.
+    pub async fn rebase(&self, options: &RebaseOptions) -> Result<RebaseOutcome> {
+        self.run_rebase(options.args()).await
+    }
+
+    ///Resume a stopped rebase once any conflicts have been resolved and staged
+    pub async fn rebase_continue(&self) -> Result<RebaseOutcome> {
+        self.run_rebase(["rebase", "--continue"]).await
+    }
+
+    ///Resume a stopped rebase, dropping the commit it stopped at
+    pub async fn rebase_skip(&self) -> Result<RebaseOutcome> {
+        self.run_rebase(["rebase", "--skip"]).await
+    }
+
+    ///Abandon a stopped rebase, restoring the branch to where it was before
+    pub async fn rebase_abort(&self) -> Result<()> {
+        self.execute_git(["rebase", "--abort"]).await
+    }
+
+    ///The progress of the rebase in progress, `None` if there isn't one
+    pub async fn rebase_state(&self) -> Result<Option<RebaseState>> {
+        for dir in rebase::STATE_DIRS {
+            let path = self
+                .execute_git_fn(["rev-parse", "--git-path", dir], parse_trimmed)
+                .await?;
+            let path = self.location.join(path);
+            if dir == "rebase-apply"
+                && !fs::try_exists(path.join(rebase::APPLY_REBASING_MARKER))
+                    .await
+                    .unwrap_or(false)
+            {
+                continue;
+            }
+            if fs::metadata(&path).await.is_ok_and(|m| m.is_dir()) {
+                let mut files = HashMap::new();
+                for name in rebase::STATE_FILES {
+                    if let Ok(contents) = fs::read_to_string(path.join(name)).await {
+                        files.insert(name, contents);
+                    }
+                }
+                return rebase::parse_state(|name| files.get(name).cloned()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    ///Stash local changes, returning the hash of the new stash, or `None` if there were no
+    ///changes to stash
+    pub async fn stash_push(&self, options: &StashOptions) -> Result<Option<String>> {
+        let before = self
+            .execute_git_fn(stash::HEAD_ARGS, parse_optional)
+            .await?;
+        self.execute_git(options.args()).await?;
+        let after = self
+            .execute_git_fn(stash::HEAD_ARGS, parse_optional)
+            .await?;
+        Ok(after.filter(|after| before.as_ref() != Some(after)))
+    }
+
+    ///List stashes, most recent first
+    pub async fn stash_list(&self) -> Result<Vec<StashEntry>> {
+        self.execute_git_fn(stash::LIST_ARGS, stash::parse_stashes)
+            .await
+    }
+
+    ///Apply the stash at the given index to the working tree, keeping it in the stash list
+    pub async fn stash_apply(&self, index: usize) -> Result<StashApplyOutcome> {
+        self.apply_stash("apply", index).await
+    }
+
+    ///Apply the stash at the given index to the working tree and drop it, unless it conflicts
+    pub async fn stash_pop(&self, index: usize) -> Result<StashApplyOutcome> {
+        self.apply_stash("pop", index).await
+    }
+
+    ///Remove the stash at the given index from the stash list
+    pub async fn stash_drop(&self, index: usize) -> Result<()> {
+        self.execute_git([
+            "stash".to_owned(),
+            "drop".to_owned(),
+            stash::reference(index),
+        ])
+        .await
+    }
+
+    ///The changes recorded in the stash at the given index, relative to the commit it was based on
+    pub async fn stash_show(&self, index: usize) -> Result<Diff> {
+        self.execute_git_fn(stash::show_args(index), diff::parse_diff)
+            .await
+    }
+
+    ///Check out another working tree at the given path, sharing this repository's objects and
+    ///refs, returning a handle to it
+    ///
+    ///A relative path is relative to this repository's location.
+    pub async fn add_worktree<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &WorktreeOptions,
+    ) -> Result<AsyncRepository> {
+        self.execute_git(options.args(path.as_ref())).await?;
+        Ok(self.related(self.location.join(path)))
+    }
+
+    ///List the main working tree followed by any linked worktrees
+    pub async fn list_worktrees(&self) -> Result<Vec<Worktree>> {
+        self.execute_git_fn(worktree::LIST_ARGS, worktree::parse_worktrees)
+            .await
+    }
+
+    ///Delete a linked worktree, forcing removes it even if it has local changes
+    pub async fn remove_worktree<P: AsRef<Path>>(&self, path: P, force: bool) -> Result<()> {
+        self.execute_git(worktree::remove_args(path.as_ref(), force))
+            .await
+    }
+
+    ///Stop a linked worktree from being pruned, moved or removed, with an optional reason
+    pub async fn lock_worktree<P: AsRef<Path>>(&self, path: P, reason: Option<&str>) -> Result<()> {
+        self.execute_git(worktree::lock_args(path.as_ref(), reason))
+            .await
+    }
+
+    ///Unlock a linked worktree so it can be pruned, moved or removed again
+    pub async fn unlock_worktree<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.execute_git(worktree::unlock_args(path.as_ref())).await
+    }
+
+    ///Clean up the administrative files of worktrees which have been deleted
+    pub async fn prune_worktrees(&self) -> Result<()> {
+        self.execute_git(["worktree", "prune"]).await
+    }
+
+    ///The submodules declared in `.gitmodules`, with the commit recorded for each and the state
+    ///of its checkout
+    pub async fn submodule_status(&self) -> Result<Vec<Submodule>> {
+        if !fs::try_exists(self.location.join(submodule::GITMODULES))
+            .await
+            .unwrap_or(false)
+        {
+            return Ok(Vec::new());
+        }
+        let mut submodules = self
+            .execute_git_fn(submodule::CONFIG_ARGS, submodule::parse_gitmodules)
+            .await?;
+        let gitlinks = self
+            .execute_git_fn(submodule::GITLINKS_ARGS, submodule::parse_gitlinks)
+            .await?;
+        let status = self
+            .execute_git_fn(submodule::STATUS_ARGS, |o| Ok(o.to_owned()))
+            .await?;
+        submodule::apply_status(&mut submodules, &gitlinks, &status)?;
+        Ok(submodules)
+    }
+
+    ///Clone, initialise or update submodules as given by the options
+    pub async fn submodule_update(&self, options: &SubmoduleUpdateOptions) -> Result<()> {
+        self.execute_git(options.args()).await
+    }
+
+    ///Add the repository at the given URL as a submodule at the given path, optionally following
+    ///a branch, returning a handle to it
+    pub async fn submodule_add<P: AsRef<Path>>(
+        &self,
+        url: &str,
+        path: P,
+        branch: Option<&str>,
+    ) -> Result<AsyncRepository> {
+        self.execute_git(submodule::add_args(url, path.as_ref(), branch))
+            .await?;
+        Ok(self.submodule(path))
+    }
+
+    ///Update the URL of each submodule's remote to match `.gitmodules`
+    pub async fn submodule_sync(&self, recursive: bool) -> Result<()> {
+        self.execute_git(submodule::sync_args(recursive)).await
+    }
+
+    ///Run the given shell command in each checked out submodule, failing if it fails in any
+    pub async fn submodule_foreach(&self, command: &str, recursive: bool) -> Result<()> {
+        self.execute_git(submodule::foreach_args(command, recursive))
+            .await
+    }
+
+    ///A handle to the submodule at the given path, run in the same way as this repository
+    pub fn submodule<P: AsRef<Path>>(&self, path: P) -> AsyncRepository {
+        self.related(self.location.join(path))
+    }
+
+    ///List commits matching the given options, most recent first
+    pub async fn log(&self, options: &LogOptions) -> Result<Vec<Commit>> {
+        self.execute_git_fn(options.args(), log::parse_log).await
+    }
+
+    ///Show changes between the working tree, the index and commits
+    pub async fn diff(&self, spec: &DiffSpec) -> Result<Diff> {
+        self.execute_git_fn(spec.patch_args(), diff::parse_diff)
+            .await
+    }
+
+    ///Summarise the number of lines changed per file, as with `git diff --numstat`
+    pub async fn diff_stat(&self, spec: &DiffSpec) -> Result<DiffStat> {
+        self.execute_git_fn(spec.numstat_args(), diff::parse_numstat)
+            .await
+    }
+
+    ///Create a tag, lightweight unless a message is given or the tag is signed
+    pub async fn create_tag(&self, name: &TagName, options: &TagOptions) -> Result<()> {
+        let mut invocation = self.invocation(options.args(name));
+        invocation.input = options.input();
+        self.run(invocation, |_| Ok(())).await
+    }
+
+    ///List tags matching the given options
+    pub async fn list_tags(&self, options: &ListTagsOptions) -> Result<Vec<Tag>> {
+        self.execute_git_fn(options.args(), tag::parse_tags).await
+    }
+
+    ///Delete a local tag
+    pub async fn delete_tag(&self, name: &TagName) -> Result<()> {
+        self.execute_git(["tag", "--delete", name.value.as_str()])
+            .await
+    }
+
+    ///Check the signature of a signed tag, failing if it is unsigned or the signature is bad
+    pub async fn verify_tag(&self, name: &TagName) -> Result<()> {
+        self.execute_git(["verify-tag", name.value.as_str()]).await
+    }
+
+    ///Push all local tags to the given remote
+    pub async fn push_tags(&self, remote: &str) -> Result<()> {
+        self.execute_git(["push", "--tags", remote]).await
+    }
+
+    /// Execute user defined command
+    pub async fn cmd<I, S>(&self, args: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.execute_git(args).await
+    }
+
+    /// Execute user defined command and return its output
+    pub async fn cmd_out<I, S>(&self, args: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.execute_git_fn(args, parse_lines).await
+    }
+
+    ///A handle to another repository found from this one, such as a linked worktree, run in the
+    ///same way as this one
+    fn related(&self, location: PathBuf) -> AsyncRepository {
+        AsyncRepository {
+            location,
+            bare: false,
+            settings: self.settings.related(),
+        }
+    }
+
+    fn invocation<I, S>(&self, args: I) -> Invocation
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.settings.invocation(&self.location, args)
+    }
+
+    ///Move this handle to the root of the repository found from its location, stopping the
+    ///search at `ceiling` if given
+    async fn locate(&mut self, ceiling: Option<PathBuf>) -> Result<()> {
+        let mut invocation = self.invocation(metadata::DISCOVER_ARGS);
+        invocation.envs.extend(
+            ceiling.map(|ceiling| ("GIT_CEILING_DIRECTORIES".into(), ceiling.into_os_string())),
+        );
+        let discovery = self.run(invocation, metadata::parse_discovery).await?;
+        self.location = if discovery.inside_work_tree {
+            PathBuf::from(
+                self.execute_git_fn(metadata::TOPLEVEL_ARGS, parse_trimmed)
+                    .await?,
+            )
+        } else {
+            discovery.git_dir
+        };
+        self.bare = discovery.bare;
+        Ok(())
+    }
+
+    async fn config_values<I, S>(&self, args: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        match self.execute_git_fn(args, config::parse_values).await {
+            Err(error) if error.exit_code() == Some(config::MISSING_EXIT_CODE) => Ok(Vec::new()),
+            result => result,
+        }
+    }
+
+    async fn apply_stash(&self, command: &str, index: usize) -> Result<StashApplyOutcome> {
+        let args = [
+            "stash".to_owned(),
+            command.to_owned(),
+            stash::reference(index),
+        ];
+        if let Err(error) = self.execute_git(args).await {
+            let conflicts = self
+                .execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)
+                .await?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(StashApplyOutcome::Conflicted { conflicts })
+            };
+        }
+        Ok(StashApplyOutcome::Applied)
+    }
+
+    async fn run_rebase<I, S>(&self, args: I) -> Result<RebaseOutcome>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut invocation = self.invocation(args);
+        invocation.envs.extend(rebase::envs());
+        match self.run(invocation, |_| Ok(())).await {
+            Ok(()) => Ok(RebaseOutcome::Completed {
+                commit: self.get_hash(false).await?,
+            }),
+            Err(error) => match self.rebase_state().await? {
+                Some(state) => Ok(RebaseOutcome::Stopped {
+                    state,
+                    conflicts: self
+                        .execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)
+                        .await?,
+                }),
+                None => Err(error),
+            },
+        }
+    }
+
+    async fn execute_git<I, S>(&self, args: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.execute_git_fn(args, |_| Ok(())).await
+    }
+
+    async fn execute_git_fn<I, S, F, R>(&self, args: I, process: F) -> Result<R>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+        F: Fn(&str) -> Result<R>,
+    {
+        self.run(self.invocation(args), process).await
+    }
+
+    async fn run<F, R>(&self, invocation: Invocation, process: F) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let output = spawn(&invocation, None)
+            .await
+            .map_err(|source| GitError::Execution { source })?;
+        process_output(&invocation, output, process)
+    }
+
+    async fn run_with_progress<F, R>(
+        &self,
+        invocation: Invocation,
+        sink: &mut (dyn ProgressSink + Send),
+        process: F,
+    ) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let mut parser = ProgressParser::new();
+        let mut on_stderr = |chunk: &[u8]| {
+            for progress in parser.feed(chunk) {
+                sink.progress(progress);
+            }
+        };
+        let output = spawn(&invocation, Some(&mut on_stderr))
+            .await
+            .map_err(|source| GitError::Execution { source })?;
+        process_output(&invocation, output, process)
+    }
+}
+
+///Called with each chunk of stderr as it is read
+type StderrCallback<'a> = &'a mut (dyn FnMut(&[u8]) + Send);
+
+async fn spawn(
+    invocation: &Invocation,
+    mut on_stderr: Option<StderrCallback<'_>>,
+) -> std::io::Result<GitOutput> {
+    if let Some(output) = runner::cancelled_before_start(invocation) {
+        return Ok(output);
+    }
+
+    let mut command = Command::from(invocation.command());
+    command
+        .stdin(if invocation.input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    let mut stdin = child.stdin.take();
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let mut stdout_buf = Vec::new();
+    let mut stderr_buf = Vec::new();
+
+    let completion = async {
+        let write = async {
+            //Like the sync runner, a failed write such as git exiting without reading all of
+            //its input is left to show in git's own exit status and stderr
+            if let (Some(input), Some(mut stdin)) = (&invocation.input, stdin.take()) {
+                let _ = stdin.write_all(input).await;
+            }
+            Ok(())
+        };
+        let read_stdout = async {
+            if let Some(stdout) = &mut stdout {
+                stdout.read_to_end(&mut stdout_buf).await?;
+            }
+            Ok(())
+        };
+        let read_stderr = async {
+            if let Some(stderr) = &mut stderr {
+                let mut chunk = [0; 8192];
+                loop {
+                    let n = stderr.read(&mut chunk).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    stderr_buf.extend_from_slice(&chunk[..n]);
+                    if let Some(on_stderr) = &mut on_stderr {
+                        on_stderr(&chunk[..n]);
+                    }
+                }
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        tokio::try_join!(write, read_stdout, read_stderr)?;
+        child.wait().await
+    };
+    let timeout = async {
+        match invocation.timeout {
+            Some(timeout) => time::sleep(timeout).await,
+            None => future::pending().await,
+        }
+    };
+    let cancellation = async {
+        match &invocation.cancellation {
+            Some(token) => {
+                while !token.is_cancelled() {
+                    time::sleep(runner::POLL_INTERVAL).await;
+                }
+            }
+            None => future::pending().await,
+        }
+    };
+
+    let (status, interrupted) = tokio::select! {
+        status = completion => (Some(status?), None),
+        _ = timeout => (None, Some(Interruption::TimedOut)),
+        _ = cancellation => (None, Some(Interruption::Cancelled)),
+    };
+    if interrupted.is_some() {
+        if let Some(pid) = pid {
+            runner::kill_process_group(pid);
+        }
+        child.kill().await?;
+    }
+
+    Ok(GitOutput {
+        exit_code: status.and_then(|s| s.code()),
+        stdout: stdout_buf,
+        stderr: stderr_buf,
+        interrupted,
+    })
+}
@@ -0,0 +1,161 @@
+use super::GitError;
+use crate::types::Result;
+
+const FIELDS: &[&str] = &[
+    "%(refname)",
+    "%(objectname)",
+    "%(HEAD)",
+    "%(symref)",
+    "%(upstream:short)",
+    "%(upstream:track,nobracket)",
+];
+
+pub(crate) fn list_args() -> Vec<String> {
+    vec![
+        String::from("for-each-ref"),
+        format!("--format={}", FIELDS.join("%00")),
+        String::from("refs/heads"),
+        String::from("refs/remotes"),
+    ]
+}
+
+///Whether a branch is local or a remote-tracking branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchKind {
+    Local,
+    Remote,
+}
+
+///A branch as returned by `Repository::list_branches_detailed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    ///The short name, such as `main` or `origin/main`
+    pub name: String,
+    pub kind: BranchKind,
+    ///The commit at the tip of the branch
+    pub commit: String,
+    ///Whether the branch is currently checked out
+    pub is_head: bool,
+    ///The short name of the upstream branch, if one is configured
+    pub upstream: Option<String>,
+    ///Whether the configured upstream branch no longer exists
+    pub upstream_gone: bool,
+    ///Commits on this branch but not its upstream, `None` if there is no upstream to compare with
+    pub ahead: Option<u32>,
+    ///Commits on the upstream but not this branch, `None` if there is no upstream to compare with
+    pub behind: Option<u32>,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse `ahead 1, behind 2` as printed by `%(upstream:track,nobracket)`
+fn parse_track(track: &str) -> Result<(u32, u32)> {
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ").filter(|p| !p.is_empty()) {
+        let mut words = part.split(' ');
+        let direction = words.next();
+        let count = words.next().and_then(|c| c.parse().ok());
+        match (direction, count, words.next()) {
+            (Some("ahead"), Some(count), None) => ahead = count,
+            (Some("behind"), Some(count), None) => behind = count,
+            _ => return Err(unexpected(track)),
+        }
+    }
+    Ok((ahead, behind))
+}
+
+///Parse the output of `git for-each-ref` using the `FIELDS` format
+pub(crate) fn parse_branches(output: &str) -> Result<Vec<Branch>> {
+    let mut branches = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\0').collect();
+        if fields.len() != FIELDS.len() {
+            return Err(unexpected(line));
+        }
+        //Skip symbolic refs such as `origin/HEAD`
+        if !fields[3].is_empty() {
+            continue;
+        }
+
+        let (kind, name) = if let Some(name) = fields[0].strip_prefix("refs/heads/") {
+            (BranchKind::Local, name)
+        } else if let Some(name) = fields[0].strip_prefix("refs/remotes/") {
+            (BranchKind::Remote, name)
+        } else {
+            return Err(unexpected(line));
+        };
+        let upstream = Some(fields[4].to_owned()).filter(|u| !u.is_empty());
+        let upstream_gone = fields[5] == "gone";
+        let (ahead, behind) = if upstream.is_some() && !upstream_gone {
+            let (ahead, behind) = parse_track(fields[5])?;
+            (Some(ahead), Some(behind))
+        } else {
+            (None, None)
+        };
+
+        branches.push(Branch {
+            name: name.to_owned(),
+            kind,
+            commit: fields[1].to_owned(),
+            is_head: fields[2] == "*",
+            upstream,
+            upstream_gone,
+            ahead,
+            behind,
+        });
+    }
+    Ok(branches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_branches() {
+        let output = concat!(
+            "refs/heads/feature\0aaaa\0 \0\0origin/feature\0ahead 2, behind 1\n",
+            "refs/heads/gone\0bbbb\0 \0\0origin/gone\0gone\n",
+            "refs/heads/main\0cccc\0*\0\0origin/main\0\n",
+            "refs/heads/scratch\0dddd\0 \0\0\0\n",
+            "refs/remotes/origin/HEAD\0cccc\0 \0refs/remotes/origin/main\0\0\n",
+            "refs/remotes/origin/main\0cccc\0 \0\0\0\n",
+        );
+
+        let branches = parse_branches(output).unwrap();
+
+        assert_eq!(5, branches.len());
+        assert_eq!(
+            Branch {
+                name: String::from("feature"),
+                kind: BranchKind::Local,
+                commit: String::from("aaaa"),
+                is_head: false,
+                upstream: Some(String::from("origin/feature")),
+                upstream_gone: false,
+                ahead: Some(2),
+                behind: Some(1),
+            },
+            branches[0]
+        );
+        assert!(branches[1].upstream_gone);
+        assert_eq!((None, None), (branches[1].ahead, branches[1].behind));
+        assert!(branches[2].is_head);
+        assert_eq!((Some(0), Some(0)), (branches[2].ahead, branches[2].behind));
+        assert_eq!(None, branches[3].upstream);
+        assert_eq!(
+            (BranchKind::Remote, "origin/main"),
+            (branches[4].kind, branches[4].name.as_str())
+        );
+    }
+
+    #[test]
+    fn test_parse_track() {
+        assert_eq!((0, 3), parse_track("behind 3").unwrap());
+        assert_eq!((1, 0), parse_track("ahead 1").unwrap());
+        assert!(parse_track("sideways 1").is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::clone::CloneOptions;
+use crate::runner::{CancellationToken, GitRunner, Invocation, ProcessRunner};
+use crate::types::{GitUrl, Result};
+use crate::Repository;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+///Settings applied to every git invocation made by a repository
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Settings {
+    git: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    env_remove: Vec<OsString>,
+    env_clear: bool,
+    config: Vec<(String, String)>,
+    git_dir: Option<PathBuf>,
+    work_tree: Option<PathBuf>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancellation: Option<CancellationToken>,
+}
+
+impl Settings {
+    pub(crate) fn invocation<P, I, S>(&self, cwd: P, args: I) -> Invocation
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut global: Vec<OsString> = Vec::new();
+        for (key, value) in &self.config {
+            global.push("-c".into());
+            global.push(format!("{}={}", key, value).into());
+        }
+        if let Some(git_dir) = &self.git_dir {
+            let mut arg = OsString::from("--git-dir=");
+            arg.push(git_dir);
+            global.push(arg);
+        }
+        if let Some(work_tree) = &self.work_tree {
+            let mut arg = OsString::from("--work-tree=");
+            arg.push(work_tree);
+            global.push(arg);
+        }
+
+        let mut invocation = Invocation::new(cwd, global);
+        invocation
+            .args
+            .extend(args.into_iter().map(|a| a.as_ref().to_owned()));
+        if let Some(git) = &self.git {
+            invocation.program = git.clone().into();
+        }
+        invocation.envs = self.envs.clone();
+        invocation.env_remove = self.env_remove.clone();
+        invocation.env_clear = self.env_clear;
+        invocation.timeout = self.timeout;
+        invocation.cancellation = self.cancellation.clone();
+        invocation
+    }
+
+    ///Settings for another repository found from this one, such as a linked worktree, without
+    ///the git directory and work tree overrides which only apply to this one
+    pub(crate) fn related(&self) -> Settings {
+        Settings {
+            git_dir: None,
+            work_tree: None,
+            ..self.clone()
+        }
+    }
+}
+
+///Configures how git is executed for a `Repository`
+///
+///```no_run
+///use rustygit::builder::RepositoryBuilder;
+///
+///let repo = RepositoryBuilder::new("/srv/checkout")
+///    .git_binary("/opt/git/bin/git")
+///    .env("GIT_TERMINAL_PROMPT", "0")
+///    .env("GIT_CONFIG_NOSYSTEM", "1")
+///    .config("core.autocrlf", "false")
+///    .build();
+///```
+pub struct RepositoryBuilder {
+    location: PathBuf,
+    settings: Settings,
+    runner: Arc<dyn GitRunner>,
+}
+
+impl RepositoryBuilder {
+    pub fn new<P: AsRef<Path>>(p: P) -> RepositoryBuilder {
+        RepositoryBuilder {
+            location: p.as_ref().to_path_buf(),
+            settings: Settings::default(),
+            runner: Arc::new(ProcessRunner),
+        }
+    }
+
+    ///Run the given git executable rather than the `git` found on `$PATH`
+    pub fn git_binary<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.settings.git = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    ///Set an environment variable for every invocation
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, key: K, value: V) -> Self {
+        self.settings
+            .envs
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    ///Remove an inherited environment variable for every invocation
+    pub fn env_remove<K: AsRef<OsStr>>(mut self, key: K) -> Self {
+        self.settings.env_remove.push(key.as_ref().to_owned());
+        self
+    }
+
+    ///Don't inherit any environment variables, only those set with `env` are passed to git
+    pub fn env_clear(mut self) -> Self {
+        self.settings.env_clear = true;
+        self
+    }
+
+    ///Override a configuration value for every invocation, as with `git -c key=value`
+    pub fn config(mut self, key: &str, value: &str) -> Self {
+        self.settings
+            .config
+            .push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    ///Use the given repository directory, as with `git --git-dir`
+    pub fn git_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.settings.git_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    ///Use the given working tree, as with `git --work-tree`
+    pub fn work_tree<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.settings.work_tree = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    ///Kill any git command that runs for longer than the given duration
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.settings.timeout = Some(timeout);
+        self
+    }
+
+    ///Kill any running git command once the given token is cancelled
+    pub fn cancellation(mut self, token: &CancellationToken) -> Self {
+        self.settings.cancellation = Some(token.clone());
+        self
+    }
+
+    ///Execute git through the given runner
+    pub fn runner(mut self, runner: Arc<dyn GitRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    ///Create a Repository struct for a pre-existing local git repository
+    pub fn build(self) -> Repository {
+        Repository {
+            location: self.location,
+            bare: false,
+            runner: self.runner,
+            settings: self.settings,
+        }
+    }
+
+    ///Find the repository containing the folder, which may be anywhere within its working tree
+    ///or git directory
+    pub fn discover(self) -> Result<Repository> {
+        let mut repo = self.build();
+        repo.locate(None)?;
+        Ok(repo)
+    }
+
+    ///Open the repository at the folder, which must be the root of its working tree or its
+    ///git directory, failing with `GitError::NotARepository` otherwise
+    pub fn open(self) -> Result<Repository> {
+        let mut repo = self.build();
+        let ceiling = fs::canonicalize(&repo.location)
+            .ok()
+            .and_then(|path| path.parent().map(|parent| parent.to_owned()));
+        repo.locate(ceiling)?;
+        Ok(repo)
+    }
+
+    ///Initialise the folder as a git repository
+    pub fn init(self) -> Result<Repository> {
+        let repo = self.build();
+        repo.execute_git(["init"])?;
+        Ok(repo)
+    }
+
+    ///Clone a remote git repository into the folder
+    pub fn clone(self, url: &GitUrl, options: &CloneOptions) -> Result<Repository> {
+        let mut repo = self.build();
+        repo.clone_into(url, options, None)?;
+        Ok(repo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_invocation() {
+        let settings = RepositoryBuilder::new("/repo")
+            .git_binary("/opt/git")
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env_remove("GIT_DIR")
+            .config("user.name", "Some One")
+            .git_dir("/repo/.git")
+            .work_tree("/repo")
+            .settings;
+
+        let invocation = settings.invocation("/repo", ["status"]);
+
+        assert_eq!(OsString::from("/opt/git"), invocation.program);
+        assert_eq!(
+            vec![
+                "-c",
+                "user.name=Some One",
+                "--git-dir=/repo/.git",
+                "--work-tree=/repo",
+                "status"
+            ],
+            invocation.args_lossy()
+        );
+        assert_eq!(
+            vec![(OsString::from("GIT_TERMINAL_PROMPT"), OsString::from("0"))],
+            invocation.envs
+        );
+        assert_eq!(vec![OsString::from("GIT_DIR")], invocation.env_remove);
+        assert!(!invocation.env_clear);
+    }
+}
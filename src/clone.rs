@@ -0,0 +1,195 @@
+use crate::types::GitUrl;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+///Options for cloning a repository with `Repository::clone_with`
+#[derive(Debug, Clone, Default)]
+pub struct CloneOptions {
+    depth: Option<u32>,
+    shallow_since: Option<String>,
+    branch: Option<String>,
+    single_branch: bool,
+    no_checkout: bool,
+    bare: bool,
+    mirror: bool,
+    filter: Option<String>,
+    recurse_submodules: bool,
+    reference: Option<PathBuf>,
+    origin: Option<String>,
+}
+
+impl CloneOptions {
+    pub fn new() -> CloneOptions {
+        CloneOptions::default()
+    }
+
+    ///Create a shallow clone with history truncated to the given number of commits
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    ///Create a shallow clone with history after the given date
+    pub fn shallow_since(mut self, date: &str) -> Self {
+        self.shallow_since = Some(date.to_owned());
+        self
+    }
+
+    ///Check out the given branch or tag rather than the remote's `HEAD`
+    pub fn branch(mut self, branch: &str) -> Self {
+        self.branch = Some(branch.to_owned());
+        self
+    }
+
+    ///Only fetch the history of a single branch
+    pub fn single_branch(mut self) -> Self {
+        self.single_branch = true;
+        self
+    }
+
+    ///Don't check out `HEAD` after cloning
+    pub fn no_checkout(mut self) -> Self {
+        self.no_checkout = true;
+        self
+    }
+
+    ///Create a bare repository with no working tree
+    pub fn bare(mut self) -> Self {
+        self.bare = true;
+        self
+    }
+
+    ///Create a bare repository mirroring all refs of the remote
+    pub fn mirror(mut self) -> Self {
+        self.mirror = true;
+        self
+    }
+
+    ///Create a partial clone using the given filter, such as `blob:none`
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_owned());
+        self
+    }
+
+    ///Initialise and clone submodules
+    pub fn recurse_submodules(mut self) -> Self {
+        self.recurse_submodules = true;
+        self
+    }
+
+    ///Borrow objects from a local reference repository
+    pub fn reference<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.reference = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    ///Name the remote something other than `origin`
+    pub fn origin(mut self, name: &str) -> Self {
+        self.origin = Some(name.to_owned());
+        self
+    }
+
+    ///Returns true if the clone will have no working tree
+    pub(crate) fn is_bare(&self) -> bool {
+        self.bare || self.mirror
+    }
+
+    pub(crate) fn args(&self, url: &GitUrl, path: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![OsString::from("clone")];
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={}", depth).into());
+        }
+        if let Some(date) = &self.shallow_since {
+            args.push(format!("--shallow-since={}", date).into());
+        }
+        if let Some(branch) = &self.branch {
+            args.push(format!("--branch={}", branch).into());
+        }
+        if self.single_branch {
+            args.push("--single-branch".into());
+        }
+        if self.no_checkout {
+            args.push("--no-checkout".into());
+        }
+        if self.bare {
+            args.push("--bare".into());
+        }
+        if self.mirror {
+            args.push("--mirror".into());
+        }
+        if let Some(filter) = &self.filter {
+            args.push(format!("--filter={}", filter).into());
+        }
+        if self.recurse_submodules {
+            args.push("--recurse-submodules".into());
+        }
+        if let Some(reference) = &self.reference {
+            args.push("--reference".into());
+            args.push(reference.into());
+        }
+        if let Some(origin) = &self.origin {
+            args.push(format!("--origin={}", origin).into());
+        }
+        args.push("--".into());
+        args.push(url.value.as_str().into());
+        args.push(path.into());
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_default_args() {
+        let url = GitUrl::from_str("https://github.com/user/project.git").unwrap();
+
+        let args = CloneOptions::new().args(&url, Path::new("project"));
+
+        assert_eq!(
+            vec![
+                "clone",
+                "--",
+                "https://github.com/user/project.git",
+                "project"
+            ],
+            args
+        );
+    }
+
+    #[test]
+    fn test_options_args() {
+        let url = GitUrl::from_str("https://github.com/user/project.git").unwrap();
+        let options = CloneOptions::new()
+            .depth(1)
+            .branch("main")
+            .single_branch()
+            .filter("blob:none")
+            .mirror()
+            .reference("/var/cache/project.git")
+            .origin("upstream");
+
+        let args = options.args(&url, Path::new("project"));
+
+        assert!(options.is_bare());
+        assert_eq!(
+            vec![
+                "clone",
+                "--depth=1",
+                "--branch=main",
+                "--single-branch",
+                "--mirror",
+                "--filter=blob:none",
+                "--reference",
+                "/var/cache/project.git",
+                "--origin=upstream",
+                "--",
+                "https://github.com/user/project.git",
+                "project",
+            ],
+            args
+        );
+    }
+}
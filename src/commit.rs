@@ -0,0 +1,271 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Text(String),
+    File(PathBuf),
+}
+
+///The signature format used when signing commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningFormat {
+    OpenPgp,
+    Ssh,
+    X509,
+}
+
+impl SigningFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            SigningFormat::OpenPgp => "openpgp",
+            SigningFormat::Ssh => "ssh",
+            SigningFormat::X509 => "x509",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Signing {
+    Default,
+    Disabled,
+    Enabled(Option<String>),
+}
+
+///Options for creating a commit with `Repository::commit`
+///
+///By default only the changes already staged in the index are committed.
+#[derive(Debug, Clone)]
+pub struct CommitBuilder {
+    message: Message,
+    all: bool,
+    allow_empty: bool,
+    amend: bool,
+    no_verify: bool,
+    author: Option<String>,
+    author_date: Option<String>,
+    committer_name: Option<String>,
+    committer_email: Option<String>,
+    committer_date: Option<String>,
+    signing: Signing,
+    signing_format: Option<SigningFormat>,
+}
+
+impl CommitBuilder {
+    ///Create a commit with the given message, which is passed to git on stdin
+    pub fn new(message: &str) -> CommitBuilder {
+        CommitBuilder::with_message(Message::Text(message.to_owned()))
+    }
+
+    ///Create a commit with the message read from the given file
+    ///
+    ///A relative path is resolved against the repository's location rather than the current
+    ///directory.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> CommitBuilder {
+        CommitBuilder::with_message(Message::File(path.as_ref().to_path_buf()))
+    }
+
+    fn with_message(message: Message) -> CommitBuilder {
+        CommitBuilder {
+            message,
+            all: false,
+            allow_empty: false,
+            amend: false,
+            no_verify: false,
+            author: None,
+            author_date: None,
+            committer_name: None,
+            committer_email: None,
+            committer_date: None,
+            signing: Signing::Default,
+            signing_format: None,
+        }
+    }
+
+    ///Stage all modified and deleted tracked files before committing
+    pub fn all(mut self) -> Self {
+        self.all = true;
+        self
+    }
+
+    ///Allow a commit with no changes
+    pub fn allow_empty(mut self) -> Self {
+        self.allow_empty = true;
+        self
+    }
+
+    ///Replace the tip of the current branch rather than creating a new commit on top of it
+    pub fn amend(mut self) -> Self {
+        self.amend = true;
+        self
+    }
+
+    ///Bypass the pre-commit and commit-msg hooks
+    pub fn no_verify(mut self) -> Self {
+        self.no_verify = true;
+        self
+    }
+
+    pub fn author(mut self, name: &str, email: &str) -> Self {
+        self.author = Some(format!("{} <{}>", name, email));
+        self
+    }
+
+    ///Set the author date, in any format understood by git
+    pub fn author_date(mut self, date: &str) -> Self {
+        self.author_date = Some(date.to_owned());
+        self
+    }
+
+    pub fn committer(mut self, name: &str, email: &str) -> Self {
+        self.committer_name = Some(name.to_owned());
+        self.committer_email = Some(email.to_owned());
+        self
+    }
+
+    ///Set the committer date, in any format understood by git
+    pub fn committer_date(mut self, date: &str) -> Self {
+        self.committer_date = Some(date.to_owned());
+        self
+    }
+
+    ///Sign the commit, optionally with a specific key rather than the configured one
+    pub fn sign(mut self, key: Option<&str>) -> Self {
+        self.signing = Signing::Enabled(key.map(|k| k.to_owned()));
+        self
+    }
+
+    ///Don't sign the commit, even if `commit.gpgSign` is set
+    pub fn no_sign(mut self) -> Self {
+        self.signing = Signing::Disabled;
+        self
+    }
+
+    ///Override the configured `gpg.format` used for signing
+    pub fn signing_format(mut self, format: SigningFormat) -> Self {
+        self.signing_format = Some(format);
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(format) = self.signing_format {
+            args.push("-c".into());
+            args.push(format!("gpg.format={}", format.as_str()).into());
+        }
+        args.push("commit".into());
+        args.push("--quiet".into());
+        if self.all {
+            args.push("--all".into());
+        }
+        if self.allow_empty {
+            args.push("--allow-empty".into());
+        }
+        if self.amend {
+            args.push("--amend".into());
+        }
+        if self.no_verify {
+            args.push("--no-verify".into());
+        }
+        if let Some(author) = &self.author {
+            args.push(format!("--author={}", author).into());
+        }
+        match &self.signing {
+            Signing::Default => {}
+            Signing::Disabled => args.push("--no-gpg-sign".into()),
+            Signing::Enabled(None) => args.push("--gpg-sign".into()),
+            Signing::Enabled(Some(key)) => args.push(format!("--gpg-sign={}", key).into()),
+        }
+        match &self.message {
+            Message::Text(_) => args.push("--file=-".into()),
+            Message::File(path) => {
+                let mut arg = OsString::from("--file=");
+                arg.push(path);
+                args.push(arg);
+            }
+        }
+        args
+    }
+
+    pub(crate) fn envs(&self) -> Vec<(OsString, OsString)> {
+        let vars = [
+            ("GIT_AUTHOR_DATE", &self.author_date),
+            ("GIT_COMMITTER_NAME", &self.committer_name),
+            ("GIT_COMMITTER_EMAIL", &self.committer_email),
+            ("GIT_COMMITTER_DATE", &self.committer_date),
+        ];
+        vars.iter()
+            .filter_map(|(key, value)| value.as_ref().map(|v| (key.into(), v.into())))
+            .collect()
+    }
+
+    pub(crate) fn input(&self) -> Option<Vec<u8>> {
+        match &self.message {
+            Message::Text(text) => Some(text.as_bytes().to_vec()),
+            Message::File(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_args() {
+        let builder = CommitBuilder::new("-starts with a dash");
+
+        assert_eq!(vec!["commit", "--quiet", "--file=-"], builder.args());
+        assert_eq!(Some(b"-starts with a dash".to_vec()), builder.input());
+        assert!(builder.envs().is_empty());
+    }
+
+    #[test]
+    fn test_options_args() {
+        let builder = CommitBuilder::from_file("msg.txt")
+            .all()
+            .amend()
+            .no_verify()
+            .author("Jane Doe", "jane@example.com")
+            .committer("John Doe", "john@example.com")
+            .committer_date("2020-01-01T00:00:00Z")
+            .sign(Some("ABCD"))
+            .signing_format(SigningFormat::Ssh);
+
+        assert_eq!(
+            vec![
+                "-c",
+                "gpg.format=ssh",
+                "commit",
+                "--quiet",
+                "--all",
+                "--amend",
+                "--no-verify",
+                "--author=Jane Doe <jane@example.com>",
+                "--gpg-sign=ABCD",
+                "--file=msg.txt",
+            ],
+            builder.args()
+        );
+        assert_eq!(None, builder.input());
+        assert_eq!(
+            vec![
+                ("GIT_COMMITTER_NAME".into(), "John Doe".into()),
+                ("GIT_COMMITTER_EMAIL".into(), "john@example.com".into()),
+                ("GIT_COMMITTER_DATE".into(), "2020-01-01T00:00:00Z".into()),
+            ] as Vec<(OsString, OsString)>,
+            builder.envs()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_message_file() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::OsStr::from_bytes(b"msg\xff.txt");
+        let args = CommitBuilder::from_file(path).args();
+
+        assert_eq!(b"--file=msg\xff.txt", args.last().unwrap().as_bytes());
+    }
+}
@@ -0,0 +1,228 @@
+use super::GitError;
+use crate::types::Result;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+///Exit code of `git config` when a key has no value
+pub(crate) const MISSING_EXIT_CODE: i32 = 1;
+///Exit code of `git config --unset-all` when a key has no value to remove
+pub(crate) const NOTHING_TO_UNSET_EXIT_CODE: i32 = 5;
+
+///Which configuration file to read or write
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigScope {
+    ///The repository's own configuration, `.git/config`
+    Local,
+    ///The current user's configuration, such as `~/.gitconfig`
+    Global,
+    ///The configuration for all users, such as `/etc/gitconfig`
+    System,
+    ///The configuration of the current worktree, which requires `extensions.worktreeConfig`
+    Worktree,
+    ///A configuration file at the given path
+    File(PathBuf),
+}
+
+impl ConfigScope {
+    fn arg(&self) -> OsString {
+        match self {
+            ConfigScope::Local => "--local".into(),
+            ConfigScope::Global => "--global".into(),
+            ConfigScope::System => "--system".into(),
+            ConfigScope::Worktree => "--worktree".into(),
+            ConfigScope::File(path) => {
+                let mut arg = OsString::from("--file=");
+                arg.push(path);
+                arg
+            }
+        }
+    }
+}
+
+///A value as returned by `Repository::config_list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    ///The key, with the section and variable names in lower case
+    pub key: String,
+    ///The value, `None` for a variable given without `=`, which git treats as true
+    pub value: Option<String>,
+    ///Where the value was read from, such as `file:.git/config` or `command line:`
+    pub origin: String,
+    ///The scope of the file the value was read from, `None` for values given on the command
+    ///line or read from an explicit file
+    pub scope: Option<ConfigScope>,
+}
+
+fn args(scope: Option<&ConfigScope>, rest: &[&str]) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["config".into()];
+    args.extend(scope.map(|s| s.arg()));
+    args.extend(rest.iter().map(|arg| arg.into()));
+    args
+}
+
+///Arguments reading the values of a key, converted by git to the given type if any
+pub(crate) fn get_args(
+    scope: Option<&ConfigScope>,
+    value_type: Option<&str>,
+    all: bool,
+    key: &str,
+) -> Vec<OsString> {
+    let type_arg = value_type.map(|t| format!("--type={}", t));
+    let mut rest = vec!["-z"];
+    rest.extend(type_arg.as_deref());
+    rest.push(if all { "--get-all" } else { "--get" });
+    rest.push(key);
+    args(scope, &rest)
+}
+
+///Arguments setting a key, replacing all its values unless `add` is given
+pub(crate) fn set_args(scope: &ConfigScope, key: &str, value: &str, add: bool) -> Vec<OsString> {
+    let mode = if add { "--add" } else { "--replace-all" };
+    args(Some(scope), &[mode, "--end-of-options", key, value])
+}
+
+pub(crate) fn unset_args(scope: &ConfigScope, key: &str) -> Vec<OsString> {
+    args(Some(scope), &["--unset-all", key])
+}
+
+pub(crate) fn list_args(scope: Option<&ConfigScope>) -> Vec<OsString> {
+    args(scope, &["--list", "-z", "--show-origin", "--show-scope"])
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the NUL terminated values printed by `git config -z --get` or `--get-all`
+pub(crate) fn parse_values(output: &str) -> Result<Vec<String>> {
+    Ok(output
+        .split_terminator('\0')
+        .map(|value| value.to_owned())
+        .collect())
+}
+
+///Parse a value printed by `git config --type=bool`
+pub(crate) fn parse_bool(value: &str) -> Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(unexpected(value)),
+    }
+}
+
+///Parse a value printed by `git config --type=int`, which expands any `k`, `m` or `g` suffix
+pub(crate) fn parse_int(value: &str) -> Result<i64> {
+    value.parse().map_err(|_| unexpected(value))
+}
+
+///Parse the output of `git config --list -z --show-origin --show-scope`, where each entry is
+///the scope, the origin and the key and value separated by a newline, each terminated by a NUL
+pub(crate) fn parse_entries(output: &str) -> Result<Vec<ConfigEntry>> {
+    let fields: Vec<&str> = output.split_terminator('\0').collect();
+    let entries = fields.chunks_exact(3);
+    if !entries.remainder().is_empty() {
+        return Err(unexpected(output));
+    }
+
+    entries
+        .map(|entry| {
+            let scope = match entry[0] {
+                "local" => Some(ConfigScope::Local),
+                "global" => Some(ConfigScope::Global),
+                "system" => Some(ConfigScope::System),
+                "worktree" => Some(ConfigScope::Worktree),
+                _ => None,
+            };
+            let (key, value) = match entry[2].split_once('\n') {
+                Some((key, value)) => (key, Some(value.to_owned())),
+                None => (entry[2], None),
+            };
+            Ok(ConfigEntry {
+                key: key.to_owned(),
+                value,
+                origin: entry[1].to_owned(),
+                scope,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            vec!["config", "-z", "--type=bool", "--get", "core.bare"],
+            get_args(None, Some("bool"), false, "core.bare")
+        );
+        assert_eq!(
+            vec![
+                "config",
+                "--file=custom.cfg",
+                "-z",
+                "--get-all",
+                "remote.origin.fetch"
+            ],
+            get_args(
+                Some(&ConfigScope::File(PathBuf::from("custom.cfg"))),
+                None,
+                true,
+                "remote.origin.fetch"
+            )
+        );
+        assert_eq!(
+            vec![
+                "config",
+                "--global",
+                "--replace-all",
+                "--end-of-options",
+                "user.name",
+                "-Jane"
+            ],
+            set_args(&ConfigScope::Global, "user.name", "-Jane", false)
+        );
+        assert_eq!(
+            vec!["config", "--local", "--unset-all", "core.hooksPath"],
+            unset_args(&ConfigScope::Local, "core.hooksPath")
+        );
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(
+            vec!["multi\nline", "single"],
+            parse_values("multi\nline\0single\0").unwrap()
+        );
+        assert!(parse_bool("true").unwrap());
+        assert!(parse_bool("yes").is_err());
+        assert_eq!(1048576, parse_int("1048576").unwrap());
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let output = concat!(
+            "global\0file:/home/jane/.gitconfig\0user.name\nJane Doe\0",
+            "local\0file:.git/config\0core.bare\nfalse\0",
+            "local\0file:.git/config\0x.implicit\0",
+            "command\0command line:\0core.autocrlf\nfalse\0",
+        );
+
+        let entries = parse_entries(output).unwrap();
+
+        assert_eq!(
+            ConfigEntry {
+                key: String::from("user.name"),
+                value: Some(String::from("Jane Doe")),
+                origin: String::from("file:/home/jane/.gitconfig"),
+                scope: Some(ConfigScope::Global),
+            },
+            entries[0]
+        );
+        assert_eq!(Some(ConfigScope::Local), entries[1].scope);
+        assert_eq!(None, entries[2].value);
+        assert_eq!(None, entries[3].scope);
+        assert!(parse_entries("local\0file:.git/config\0").is_err());
+    }
+}
@@ -0,0 +1,736 @@
+use super::GitError;
+use crate::types::Result;
+
+///How a file changed between the two sides of a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDiffStatus {
+    Added,
+    Deleted,
+    Modified,
+    ///Renamed with the given similarity percentage
+    Renamed(u8),
+    ///Copied with the given similarity percentage
+    Copied(u8),
+    TypeChanged,
+    Unmerged,
+}
+
+///The kind of a single line within a hunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+///A single line within a hunk, without its leading marker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub content: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+}
+
+///A contiguous region of changes within a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    ///The text following the closing `@@`, usually the enclosing function
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+///The changes made to a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    ///The path before the change, `None` for added files
+    pub old_path: Option<String>,
+    ///The path after the change, `None` for deleted files
+    pub new_path: Option<String>,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub status: FileDiffStatus,
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+///The result of `Repository::diff`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diff {
+    pub files: Vec<FileDiff>,
+}
+
+///Line counts for a single file, as reported by `git diff --numstat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: String,
+    ///The path this file was renamed or copied from, if any
+    pub original_path: Option<String>,
+    ///`None` for binary files
+    pub insertions: Option<u32>,
+    ///`None` for binary files
+    pub deletions: Option<u32>,
+}
+
+///The result of `Repository::diff_stat`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffStat {
+    pub files: Vec<FileStat>,
+}
+
+impl DiffStat {
+    pub fn insertions(&self) -> u32 {
+        self.files.iter().filter_map(|f| f.insertions).sum()
+    }
+
+    pub fn deletions(&self) -> u32 {
+        self.files.iter().filter_map(|f| f.deletions).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiffTarget {
+    WorktreeToIndex,
+    IndexToHead,
+    Commits(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Detection {
+    Default,
+    Disabled,
+    Enabled(Option<u8>),
+}
+
+///What to compare in `Repository::diff` and `Repository::diff_stat`
+#[derive(Debug, Clone)]
+pub struct DiffSpec {
+    target: DiffTarget,
+    paths: Vec<String>,
+    renames: Detection,
+    copies: Detection,
+    context_lines: Option<u32>,
+}
+
+impl DiffSpec {
+    fn with_target(target: DiffTarget) -> DiffSpec {
+        DiffSpec {
+            target,
+            paths: Vec::new(),
+            renames: Detection::Default,
+            copies: Detection::Default,
+            context_lines: None,
+        }
+    }
+
+    ///Compare the working tree with the index
+    pub fn worktree_to_index() -> DiffSpec {
+        DiffSpec::with_target(DiffTarget::WorktreeToIndex)
+    }
+
+    ///Compare the index with `HEAD`
+    pub fn index_to_head() -> DiffSpec {
+        DiffSpec::with_target(DiffTarget::IndexToHead)
+    }
+
+    ///Compare two commits, or any other tree-ish
+    pub fn commits(from: &str, to: &str) -> DiffSpec {
+        DiffSpec::with_target(DiffTarget::Commits(from.to_owned(), to.to_owned()))
+    }
+
+    ///Only include changes to the given path
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_owned());
+        self
+    }
+
+    ///Detect renames, optionally with a similarity threshold percentage
+    pub fn find_renames(mut self, threshold: Option<u8>) -> Self {
+        self.renames = Detection::Enabled(threshold);
+        self
+    }
+
+    ///Detect copies as well as renames, optionally with a similarity threshold percentage
+    pub fn find_copies(mut self, threshold: Option<u8>) -> Self {
+        self.copies = Detection::Enabled(threshold);
+        self
+    }
+
+    ///Report renames as a deletion and an addition
+    pub fn no_renames(mut self) -> Self {
+        self.renames = Detection::Disabled;
+        self.copies = Detection::Default;
+        self
+    }
+
+    ///Number of unchanged lines to show around each change
+    pub fn context_lines(mut self, lines: u32) -> Self {
+        self.context_lines = Some(lines);
+        self
+    }
+
+    fn args(&self, format: &[&str]) -> Vec<String> {
+        let mut args = vec![
+            String::from("diff"),
+            String::from("--no-color"),
+            String::from("--no-ext-diff"),
+            String::from("--no-textconv"),
+            String::from("-z"),
+        ];
+        args.extend(format.iter().map(|arg| arg.to_string()));
+        match self.renames {
+            Detection::Default => {}
+            Detection::Disabled => args.push(String::from("--no-renames")),
+            Detection::Enabled(None) => args.push(String::from("-M")),
+            Detection::Enabled(Some(threshold)) => args.push(format!("-M{}%", threshold)),
+        }
+        match self.copies {
+            Detection::Enabled(None) => args.push(String::from("-C")),
+            Detection::Enabled(Some(threshold)) => args.push(format!("-C{}%", threshold)),
+            _ => {}
+        }
+        if let Some(lines) = self.context_lines {
+            args.push(format!("-U{}", lines));
+        }
+        match &self.target {
+            DiffTarget::WorktreeToIndex => {}
+            DiffTarget::IndexToHead => args.push(String::from("--cached")),
+            DiffTarget::Commits(from, to) => {
+                args.push(String::from("--end-of-options"));
+                args.push(from.clone());
+                args.push(to.clone());
+            }
+        }
+        args.push(String::from("--"));
+        args.extend(self.paths.iter().cloned());
+        args
+    }
+
+    pub(crate) fn patch_args(&self) -> Vec<String> {
+        //Explicit prefixes so `diff.noprefix` and `diff.mnemonicPrefix` can't change the headers
+        self.args(&["--raw", "--patch", "--src-prefix=a/", "--dst-prefix=b/"])
+    }
+
+    pub(crate) fn numstat_args(&self) -> Vec<String> {
+        self.args(&["--numstat"])
+    }
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+fn parse_score(score: &str) -> Result<u8> {
+    score.parse().map_err(|_| unexpected(score))
+}
+
+fn parse_status(status: &str) -> Result<FileDiffStatus> {
+    let (code, score) = status.split_at(status.chars().next().map_or(0, |c| c.len_utf8()));
+    match code {
+        "A" => Ok(FileDiffStatus::Added),
+        "D" => Ok(FileDiffStatus::Deleted),
+        "M" => Ok(FileDiffStatus::Modified),
+        "R" => Ok(FileDiffStatus::Renamed(parse_score(score)?)),
+        "C" => Ok(FileDiffStatus::Copied(parse_score(score)?)),
+        "T" => Ok(FileDiffStatus::TypeChanged),
+        "U" => Ok(FileDiffStatus::Unmerged),
+        _ => Err(unexpected(status)),
+    }
+}
+
+fn null_mode(mode: &str) -> Option<String> {
+    if mode.chars().all(|c| c == '0') {
+        None
+    } else {
+        Some(mode.to_owned())
+    }
+}
+
+///Parse the `--raw -z` records at the start of the output, returning them with the remaining patch text
+fn parse_raw(output: &str) -> Result<(Vec<FileDiff>, &str)> {
+    let mut files = Vec::new();
+    let mut rest = output;
+
+    while let Some(record) = rest.strip_prefix(':') {
+        let (meta, after) = split_field(record)?;
+        let fields: Vec<&str> = meta.split(' ').collect();
+        if fields.len() != 5 {
+            return Err(unexpected(meta));
+        }
+        let status = parse_status(fields[4])?;
+        let (first, after) = split_field(after)?;
+        let (old_path, new_path, after) = match status {
+            FileDiffStatus::Renamed(_) | FileDiffStatus::Copied(_) => {
+                let (second, after) = split_field(after)?;
+                (Some(first), Some(second), after)
+            }
+            FileDiffStatus::Added => (None, Some(first), after),
+            FileDiffStatus::Deleted => (Some(first), None, after),
+            _ => (Some(first), Some(first), after),
+        };
+        files.push(FileDiff {
+            old_path: old_path.map(|p| p.to_owned()),
+            new_path: new_path.map(|p| p.to_owned()),
+            old_mode: null_mode(fields[0]),
+            new_mode: null_mode(fields[1]),
+            status,
+            binary: false,
+            hunks: Vec::new(),
+        });
+        rest = after;
+    }
+
+    Ok((files, rest.strip_prefix('\0').unwrap_or(rest)))
+}
+
+fn split_field(s: &str) -> Result<(&str, &str)> {
+    match s.find('\0') {
+        Some(end) => Ok((&s[..end], &s[end + 1..])),
+        None => Err(unexpected(s)),
+    }
+}
+
+fn parse_range(range: &str) -> Result<(u32, u32)> {
+    let mut parts = range.splitn(2, ',');
+    let start = parts.next().unwrap_or_default();
+    let start = start.parse().map_err(|_| unexpected(range))?;
+    let count = match parts.next() {
+        Some(count) => count.parse().map_err(|_| unexpected(range))?,
+        None => 1,
+    };
+    Ok((start, count))
+}
+
+fn parse_hunk_header(line: &str) -> Result<Hunk> {
+    let rest = line.strip_prefix("@@ -").ok_or_else(|| unexpected(line))?;
+    let end = rest.find(" @@").ok_or_else(|| unexpected(line))?;
+    let mut ranges = rest[..end].split(" +");
+    let old = ranges.next().ok_or_else(|| unexpected(line))?;
+    let new = ranges.next().ok_or_else(|| unexpected(line))?;
+    let (old_start, old_lines) = parse_range(old)?;
+    let (new_start, new_lines) = parse_range(new)?;
+    Ok(Hunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        header: rest[end + 3..].trim_start().to_owned(),
+        lines: Vec::new(),
+    })
+}
+
+fn parse_patch(patch: &str, file: &mut FileDiff) -> Result<()> {
+    let mut old_lineno = 0;
+    let mut new_lineno = 0;
+
+    //Not `lines()`, which would strip the `\r` from lines ending in CRLF
+    for line in patch.split_terminator('\n') {
+        if line.starts_with("@@ ") {
+            let hunk = parse_hunk_header(line)?;
+            old_lineno = hunk.old_start;
+            new_lineno = hunk.new_start;
+            file.hunks.push(hunk);
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            let (kind, content) = match line.chars().next() {
+                Some('+') => (LineKind::Addition, &line[1..]),
+                Some('-') => (LineKind::Deletion, &line[1..]),
+                Some(' ') => (LineKind::Context, &line[1..]),
+                Some('\\') => continue,
+                _ => (LineKind::Context, line),
+            };
+            let (old, new) = match kind {
+                LineKind::Addition => (None, Some(new_lineno)),
+                LineKind::Deletion => (Some(old_lineno), None),
+                LineKind::Context => (Some(old_lineno), Some(new_lineno)),
+            };
+            if old.is_some() {
+                old_lineno += 1;
+            }
+            if new.is_some() {
+                new_lineno += 1;
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                content: content.to_owned(),
+                old_lineno: old,
+                new_lineno: new,
+            });
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_file_header(line: &str) -> bool {
+    line.starts_with("diff --git ") || line.starts_with("diff --cc ")
+}
+
+///Split the patch text into one section per file
+fn split_patches(patch: &str) -> Vec<String> {
+    let mut sections: Vec<String> = Vec::new();
+    for line in patch.split_inclusive('\n') {
+        match sections.last_mut() {
+            Some(section) if !is_file_header(line) => section.push_str(line),
+            _ => sections.push(line.to_owned()),
+        }
+    }
+    sections
+}
+
+///Parse a path from a patch header, which git quotes C-style if it has unusual characters,
+///returning it with the rest of the header
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let quoted = match s.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return Some((s.to_owned(), "")),
+    };
+    let mut bytes = Vec::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let path = String::from_utf8_lossy(&bytes).into_owned();
+                return Some((path, &quoted[i + 1..]));
+            }
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                let byte = match escaped {
+                    'a' => 0x07,
+                    'b' => 0x08,
+                    'f' => 0x0c,
+                    'n' => b'\n',
+                    'r' => b'\r',
+                    't' => b'\t',
+                    'v' => 0x0b,
+                    '0'..='7' => {
+                        let mut octal = escaped.to_string();
+                        octal.push(chars.next()?.1);
+                        octal.push(chars.next()?.1);
+                        u8::from_str_radix(&octal, 8).ok()?
+                    }
+                    _ => escaped as u8,
+                };
+                bytes.push(byte);
+            }
+            _ => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    None
+}
+
+///The path in a `---`, `+++`, `rename` or `copy` header, which git ends with a tab if it has
+///spaces
+fn header_path(path: &str, prefix: &str) -> Option<String> {
+    let path = path.strip_suffix('\t').unwrap_or(path);
+    if path == "/dev/null" {
+        return None;
+    }
+    let (path, _) = parse_quoted(path)?;
+    match path.strip_prefix(prefix) {
+        Some(stripped) => Some(stripped.to_owned()),
+        None => Some(path),
+    }
+}
+
+///The path in a `diff --git a/<path> b/<path>` header, which is ambiguous when the path has
+///spaces unless it is the same on both sides
+fn git_header_path(paths: &str) -> Option<String> {
+    if paths.starts_with('"') {
+        let (old, _) = parse_quoted(paths)?;
+        return old.strip_prefix("a/").map(|path| path.to_owned());
+    }
+    let middle = paths.len() / 2;
+    if !paths.is_char_boundary(middle) {
+        return None;
+    }
+    let (old, new) = paths.split_at(middle);
+    let path = old.strip_prefix("a/")?;
+    if new.strip_prefix(" b/")? == path {
+        Some(path.to_owned())
+    } else {
+        None
+    }
+}
+
+///The path a section of the patch is for, the new path unless the file was deleted
+fn section_path(section: &str) -> Option<String> {
+    let mut header = None;
+    let mut old = None;
+    for line in section.lines() {
+        if line.starts_with("@@ ") {
+            break;
+        } else if let Some(path) = line.strip_prefix("diff --git ") {
+            header = git_header_path(path);
+        } else if let Some(path) = line.strip_prefix("diff --cc ") {
+            header = header_path(path, "");
+        } else if let Some(path) = line
+            .strip_prefix("rename to ")
+            .or_else(|| line.strip_prefix("copy to "))
+        {
+            return header_path(path, "");
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            old = header_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(path) = header_path(path, "b/") {
+                return Some(path);
+            }
+        }
+    }
+    old.or(header)
+}
+
+///Parse the output of `git diff --raw --patch -z`
+///
+///A type change has two sections in the patch, deleting the old file and adding the new one,
+///so sections are matched to the raw records by path rather than position.
+pub(crate) fn parse_diff(output: &str) -> Result<Diff> {
+    let (mut files, patch) = parse_raw(output)?;
+
+    for section in split_patches(patch) {
+        let path = section_path(&section).ok_or_else(|| unexpected(&section))?;
+        let file = files
+            .iter_mut()
+            .find(|f| f.new_path.as_ref().or(f.old_path.as_ref()) == Some(&path))
+            .ok_or_else(|| unexpected(&section))?;
+        parse_patch(&section, file)?;
+    }
+
+    Ok(Diff { files })
+}
+
+fn parse_count(count: &str) -> Result<Option<u32>> {
+    if count == "-" {
+        Ok(None)
+    } else {
+        count.parse().map(Some).map_err(|_| unexpected(count))
+    }
+}
+
+///Parse the output of `git diff --numstat -z`
+pub(crate) fn parse_numstat(output: &str) -> Result<DiffStat> {
+    let mut files = Vec::new();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+
+    while let Some(record) = records.next() {
+        let fields: Vec<&str> = record.splitn(3, '\t').collect();
+        if fields.len() != 3 {
+            return Err(unexpected(record));
+        }
+        let (path, original_path) = if fields[2].is_empty() {
+            let original = records.next().ok_or_else(|| unexpected(record))?;
+            let path = records.next().ok_or_else(|| unexpected(record))?;
+            (path.to_owned(), Some(original.to_owned()))
+        } else {
+            (fields[2].to_owned(), None)
+        };
+        files.push(FileStat {
+            path,
+            original_path,
+            insertions: parse_count(fields[0])?,
+            deletions: parse_count(fields[1])?,
+        });
+    }
+
+    Ok(DiffStat { files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff() {
+        let output = concat!(
+            ":100644 100644 bdc955b 0000000 M\0bin\0",
+            ":100644 100755 de98044 0000000 M\0f\0",
+            ":100644 100644 587be6b 587be6b R100\0sp ace\0sp ace2\0",
+            ":000000 100644 0000000 e69de29 A\0new\0",
+            "\0",
+            "diff --git a/bin b/bin\n",
+            "index bdc955b..8835708 100644\n",
+            "Binary files a/bin and b/bin differ\n",
+            "diff --git a/f b/f\n",
+            "old mode 100644\n",
+            "new mode 100755\n",
+            "index de98044..a7bc997\n",
+            "--- a/f\n",
+            "+++ b/f\n",
+            "@@ -1,3 +1,4 @@ fn main\n",
+            " a\n",
+            "-b\n",
+            "+B\n",
+            " c\n",
+            "+d\n",
+            "\\ No newline at end of file\n",
+            "diff --git a/sp ace b/sp ace2\n",
+            "similarity index 100%\n",
+            "rename from sp ace\n",
+            "rename to sp ace2\n",
+            "diff --git a/new b/new\n",
+            "new file mode 100644\n",
+            "index 0000000..e69de29\n",
+        );
+
+        let diff = parse_diff(output).unwrap();
+
+        assert_eq!(4, diff.files.len());
+
+        assert!(diff.files[0].binary);
+        assert!(diff.files[0].hunks.is_empty());
+
+        let f = &diff.files[1];
+        assert_eq!(FileDiffStatus::Modified, f.status);
+        assert_eq!(Some(String::from("100644")), f.old_mode);
+        assert_eq!(Some(String::from("100755")), f.new_mode);
+        assert_eq!(1, f.hunks.len());
+        let hunk = &f.hunks[0];
+        assert_eq!((1, 3, 1, 4), (hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines));
+        assert_eq!("fn main", hunk.header);
+        assert_eq!(5, hunk.lines.len());
+        assert_eq!(
+            DiffLine {
+                kind: LineKind::Deletion,
+                content: String::from("b"),
+                old_lineno: Some(2),
+                new_lineno: None,
+            },
+            hunk.lines[1]
+        );
+        assert_eq!(
+            DiffLine {
+                kind: LineKind::Addition,
+                content: String::from("d"),
+                old_lineno: None,
+                new_lineno: Some(4),
+            },
+            hunk.lines[4]
+        );
+
+        let renamed = &diff.files[2];
+        assert_eq!(FileDiffStatus::Renamed(100), renamed.status);
+        assert_eq!(Some(String::from("sp ace")), renamed.old_path);
+        assert_eq!(Some(String::from("sp ace2")), renamed.new_path);
+
+        let added = &diff.files[3];
+        assert_eq!(FileDiffStatus::Added, added.status);
+        assert_eq!(None, added.old_path);
+        assert_eq!(None, added.old_mode);
+    }
+
+    #[test]
+    fn test_parse_type_change() {
+        let output = concat!(
+            ":100644 120000 bdc955b 0000000 T\0a\0",
+            ":100644 100644 de98044 0000000 M\0z\0",
+            "\0",
+            "diff --git a/a b/a\n",
+            "deleted file mode 100644\n",
+            "index bdc955b..0000000\n",
+            "--- a/a\n",
+            "+++ /dev/null\n",
+            "@@ -1 +0,0 @@\n",
+            "-text\r\n",
+            "diff --git a/a b/a\n",
+            "new file mode 120000\n",
+            "index 0000000..8835708\n",
+            "--- /dev/null\n",
+            "+++ b/a\n",
+            "@@ -0,0 +1 @@\n",
+            "+z\n",
+            "\\ No newline at end of file\n",
+            "diff --git \"a/z\" \"b/z\"\n",
+            "index de98044..a7bc997 100644\n",
+            "--- \"a/z\"\n",
+            "+++ \"b/z\"\n",
+            "@@ -1 +1 @@\n",
+            "-old\r\n",
+            "+new\r\n",
+        );
+
+        let diff = parse_diff(output).unwrap();
+
+        let changed = &diff.files[0];
+        assert_eq!(FileDiffStatus::TypeChanged, changed.status);
+        assert_eq!(2, changed.hunks.len());
+        assert_eq!(LineKind::Deletion, changed.hunks[0].lines[0].kind);
+        assert_eq!("text\r", changed.hunks[0].lines[0].content);
+        assert_eq!(LineKind::Addition, changed.hunks[1].lines[0].kind);
+        assert_eq!("z", changed.hunks[1].lines[0].content);
+
+        let modified = &diff.files[1];
+        assert_eq!(1, modified.hunks.len());
+        assert_eq!("old\r", modified.hunks[0].lines[0].content);
+        assert_eq!("new\r", modified.hunks[0].lines[1].content);
+    }
+
+    #[test]
+    fn test_section_path() {
+        assert_eq!(
+            Some(String::from("sp ace")),
+            section_path(
+                "diff --git a/sp ace b/sp ace\nBinary files a/sp ace and b/sp ace differ\n"
+            )
+        );
+        assert_eq!(
+            Some(String::from("new name")),
+            section_path("diff --git a/a b b/new name\nrename from a b\nrename to new name\n")
+        );
+        assert_eq!(
+            Some(String::from("gone one")),
+            section_path("diff --git a/gone one b/gone one\n--- a/gone one\t\n+++ /dev/null\n")
+        );
+        assert_eq!(
+            Some(String::from("caf\u{e9}\t")),
+            section_path("diff --git \"a/caf\\303\\251\\t\" \"b/caf\\303\\251\\t\"\n")
+        );
+        assert_eq!(
+            Some(String::from("conflict")),
+            section_path("diff --cc conflict\n")
+        );
+        assert!(parse_diff(":100644 100644 bdc955b 0000000 M\0f\0\0diff --git a/g b/g\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_empty_diff() {
+        assert_eq!(Diff::default(), parse_diff("").unwrap());
+    }
+
+    #[test]
+    fn test_parse_numstat() {
+        let output = concat!("1\t2\tf\0", "-\t-\tbin\0", "0\t0\t\0old name\0new name\0");
+
+        let stat = parse_numstat(output).unwrap();
+
+        assert_eq!(
+            vec![
+                FileStat {
+                    path: String::from("f"),
+                    original_path: None,
+                    insertions: Some(1),
+                    deletions: Some(2),
+                },
+                FileStat {
+                    path: String::from("bin"),
+                    original_path: None,
+                    insertions: None,
+                    deletions: None,
+                },
+                FileStat {
+                    path: String::from("new name"),
+                    original_path: Some(String::from("old name")),
+                    insertions: Some(0),
+                    deletions: Some(0),
+                },
+            ],
+            stat.files
+        );
+        assert_eq!(1, stat.insertions());
+        assert_eq!(2, stat.deletions());
+    }
+}
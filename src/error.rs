@@ -1,3 +1,7 @@
+use regex::Regex;
+use std::io;
+use std::sync::OnceLock;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -5,7 +9,7 @@ pub enum GitError {
     #[error("Unable to access current working directory")]
     WorkingDirectoryInaccessible,
     #[error("Unable to execute git process")]
-    Execution,
+    Execution { source: io::Error },
     #[error("Unable to decode error from git executable")]
     Undecodable,
     #[error("git URL is invalid")]
@@ -13,10 +17,508 @@ pub enum GitError {
     #[error("Ref name is invalid")]
     InvalidRefName,
     #[error("git failed with the following stdout: {stdout} stderr: {stderr}")]
-    GitError{
+    GitError {
         stdout: String,
-        stderr: String
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
     },
     #[error("No Git remote repository is available")]
-    NoRemoteRepositorySet
+    NoRemoteRepositorySet,
+    #[error("Unable to parse output from git executable: {0}")]
+    UnexpectedOutput(String),
+    #[error("Not a git repository: {stderr}")]
+    NotARepository {
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Merge conflict in {paths:?}")]
+    MergeConflict {
+        paths: Vec<String>,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Push of {refs:?} rejected as it is not a fast-forward")]
+    NonFastForward {
+        refs: Vec<String>,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Authentication failed: {stderr}")]
+    AuthenticationFailed {
+        url: Option<String>,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Ref not found: {reference}")]
+    RefNotFound {
+        reference: String,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Pathspec did not match any files: {pathspecs:?}")]
+    PathspecNotMatched {
+        pathspecs: Vec<String>,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Nothing to commit")]
+    NothingToCommit {
+        stdout: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Local changes to {paths:?} would be overwritten")]
+    DirtyWorktree {
+        paths: Vec<String>,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Lock file {path} exists, another git process may be running")]
+    LockFileExists {
+        path: String,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("Branch {branch} is not fully merged")]
+    BranchNotMerged {
+        branch: String,
+        stderr: String,
+        command: String,
+        exit_code: Option<i32>,
+    },
+    #[error("git was killed after running for longer than {timeout:?}")]
+    Timeout {
+        timeout: Duration,
+        ///Output written before git was killed
+        stdout: String,
+        stderr: String,
+        command: String,
+    },
+    #[error("git was killed as the command was cancelled")]
+    Cancelled {
+        ///Output written before git was killed
+        stdout: String,
+        stderr: String,
+        command: String,
+    },
+}
+
+impl GitError {
+    ///The command line of the git invocation that failed, if the error was reported by git
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            GitError::GitError { command, .. }
+            | GitError::NotARepository { command, .. }
+            | GitError::MergeConflict { command, .. }
+            | GitError::NonFastForward { command, .. }
+            | GitError::AuthenticationFailed { command, .. }
+            | GitError::RefNotFound { command, .. }
+            | GitError::PathspecNotMatched { command, .. }
+            | GitError::NothingToCommit { command, .. }
+            | GitError::DirtyWorktree { command, .. }
+            | GitError::LockFileExists { command, .. }
+            | GitError::BranchNotMerged { command, .. }
+            | GitError::Timeout { command, .. }
+            | GitError::Cancelled { command, .. } => Some(command),
+            _ => None,
+        }
+    }
+
+    ///The exit code of the git invocation that failed, `None` if it was killed by a signal
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            GitError::GitError { exit_code, .. }
+            | GitError::NotARepository { exit_code, .. }
+            | GitError::MergeConflict { exit_code, .. }
+            | GitError::NonFastForward { exit_code, .. }
+            | GitError::AuthenticationFailed { exit_code, .. }
+            | GitError::RefNotFound { exit_code, .. }
+            | GitError::PathspecNotMatched { exit_code, .. }
+            | GitError::NothingToCommit { exit_code, .. }
+            | GitError::DirtyWorktree { exit_code, .. }
+            | GitError::LockFileExists { exit_code, .. }
+            | GitError::BranchNotMerged { exit_code, .. } => *exit_code,
+            _ => None,
+        }
+    }
+}
+
+///The patterns `classify` extracts details with, compiled on first use
+struct Patterns {
+    lock_file: Regex,
+    conflict: Regex,
+    rejected: Regex,
+    auth_url: Regex,
+    pathspec: Regex,
+    missing_ref: Regex,
+    not_merged: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        lock_file: Regex::new(r"Unable to create '(.+?\.lock)': File exists").unwrap(),
+        conflict: Regex::new(
+            r"(?m)^CONFLICT \([^)]*\): (?:Merge conflict in (.+)|(.+?) deleted in )",
+        )
+        .unwrap(),
+        rejected: Regex::new(
+            r"(?m)^ ! \[rejected\]\s+\S+ -> (\S+) \((?:non-fast-forward|fetch first)\)",
+        )
+        .unwrap(),
+        auth_url: Regex::new(
+            r"(?:Authentication failed for|could not read (?:Username|Password) for) '([^']+)'",
+        )
+        .unwrap(),
+        pathspec: Regex::new(r"pathspec '(.+?)' did not match any file").unwrap(),
+        missing_ref: Regex::new(concat!(
+            r"ambiguous argument '(.+?)': unknown revision|",
+            r"couldn't find remote ref (\S+)|",
+            r"invalid reference: (\S+)|",
+            r"bad revision '(.+?)'|",
+            r"branch '(.+?)' not found|",
+            r"tag '(.+?)' not found|",
+            r"'(.+?)' - not a valid ref"
+        ))
+        .unwrap(),
+        not_merged: Regex::new(r"The branch '(.+?)' is not fully merged").unwrap(),
+    })
+}
+
+fn captures(re: &Regex, text: &str) -> Vec<String> {
+    re.captures_iter(text)
+        .filter_map(|c| c.iter().skip(1).flatten().next())
+        .map(|m| m.as_str().to_owned())
+        .collect()
+}
+
+fn first_capture(re: &Regex, text: &str) -> Option<String> {
+    captures(re, text).into_iter().next()
+}
+
+///Lines indented with a tab following a line matching `header`, as git uses to list affected paths
+fn listed_paths(header: &str, text: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut in_list = false;
+    for line in text.lines() {
+        if line.contains(header) {
+            in_list = true;
+        } else if let Some(path) = line.strip_prefix('\t').filter(|_| in_list) {
+            paths.push(path.to_owned());
+        } else {
+            in_list = false;
+        }
+    }
+    paths
+}
+
+///Turn the output of a failed git invocation into the most specific error available
+pub(crate) fn classify(
+    command: String,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+) -> GitError {
+    let patterns = patterns();
+
+    if let Some(path) = first_capture(&patterns.lock_file, &stderr) {
+        return GitError::LockFileExists {
+            path,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    if stderr.contains("not a git repository") && !stderr.contains("does not appear to be") {
+        return GitError::NotARepository {
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let dirty = [
+        "Your local changes to the following files would be overwritten",
+        "untracked working tree files would be overwritten",
+    ];
+    if dirty.iter().any(|header| stderr.contains(header)) {
+        let paths = dirty
+            .iter()
+            .flat_map(|header| listed_paths(header, &stderr))
+            .collect();
+        return GitError::DirtyWorktree {
+            paths,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let conflicts = format!("{}\n{}", stdout, stderr);
+    if conflicts.lines().any(|line| line.starts_with("CONFLICT (")) {
+        let paths = captures(&patterns.conflict, &conflicts);
+        return GitError::MergeConflict {
+            paths,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let rejected = captures(&patterns.rejected, &stderr);
+    if !rejected.is_empty() {
+        return GitError::NonFastForward {
+            refs: rejected,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let auth_failures = [
+        "Authentication failed",
+        "Permission denied (publickey",
+        "could not read Username",
+        "could not read Password",
+        "terminal prompts disabled",
+    ];
+    if auth_failures.iter().any(|failure| stderr.contains(failure)) {
+        let url = first_capture(&patterns.auth_url, &stderr);
+        return GitError::AuthenticationFailed {
+            url,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let pathspecs = captures(&patterns.pathspec, &stderr);
+    if !pathspecs.is_empty() {
+        return GitError::PathspecNotMatched {
+            pathspecs,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let missing_ref = first_capture(&patterns.missing_ref, &stderr);
+    if let Some(reference) = missing_ref {
+        return GitError::RefNotFound {
+            reference,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    if let Some(branch) = first_capture(&patterns.not_merged, &stderr) {
+        return GitError::BranchNotMerged {
+            branch,
+            stderr,
+            command,
+            exit_code,
+        };
+    }
+
+    let nothing_to_commit = [
+        "nothing to commit",
+        "no changes added to commit",
+        "nothing added to commit",
+    ];
+    if nothing_to_commit
+        .iter()
+        .any(|message| stdout.contains(message))
+    {
+        return GitError::NothingToCommit {
+            stdout,
+            command,
+            exit_code,
+        };
+    }
+
+    GitError::GitError {
+        stdout,
+        stderr,
+        command,
+        exit_code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify_stderr(stderr: &str) -> GitError {
+        classify(
+            String::from("git test"),
+            Some(128),
+            String::new(),
+            String::from(stderr),
+        )
+    }
+
+    #[test]
+    fn test_generic_error() {
+        let error = classify_stderr("fatal: something unusual happened\n");
+
+        assert_eq!(Some("git test"), error.command());
+        assert_eq!(Some(128), error.exit_code());
+        assert!(matches!(error, GitError::GitError { .. }));
+    }
+
+    #[test]
+    fn test_lock_file_exists() {
+        let error = classify_stderr(
+            "fatal: Unable to create '/tmp/repo/.git/index.lock': File exists.\n\nAnother git process seems to be running in this repository\n",
+        );
+
+        match error {
+            GitError::LockFileExists { path, .. } => assert_eq!("/tmp/repo/.git/index.lock", path),
+            e => panic!("Expected LockFileExists, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_branch_not_merged() {
+        let error = classify_stderr(
+            "error: The branch 'feature' is not fully merged.\nIf you are sure you want to delete it, run 'git branch -D feature'.\n",
+        );
+
+        match error {
+            GitError::BranchNotMerged { branch, .. } => assert_eq!("feature", branch),
+            e => panic!("Expected BranchNotMerged, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_not_a_repository() {
+        let error = classify_stderr(
+            "fatal: not a git repository (or any of the parent directories): .git\n",
+        );
+
+        assert!(matches!(error, GitError::NotARepository { .. }));
+    }
+
+    #[test]
+    fn test_dirty_worktree() {
+        let error = classify_stderr(concat!(
+            "error: Your local changes to the following files would be overwritten by checkout:\n",
+            "\tsomefile\n",
+            "\tanother file\n",
+            "Please commit your changes or stash them before you switch branches.\n",
+            "Aborting\n",
+        ));
+
+        match error {
+            GitError::DirtyWorktree { paths, .. } => {
+                assert_eq!(vec!["somefile", "another file"], paths)
+            }
+            e => panic!("Expected DirtyWorktree, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let error = classify(
+            String::from("git merge other"),
+            Some(1),
+            String::from(concat!(
+                "Auto-merging somefile\n",
+                "CONFLICT (content): Merge conflict in somefile\n",
+                "CONFLICT (modify/delete): gone deleted in HEAD and modified in other.\n",
+                "Automatic merge failed; fix conflicts and then commit the result.\n",
+            )),
+            String::new(),
+        );
+
+        match error {
+            GitError::MergeConflict { paths, .. } => assert_eq!(vec!["somefile", "gone"], paths),
+            e => panic!("Expected MergeConflict, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_non_fast_forward() {
+        let error = classify_stderr(concat!(
+            "To github.com:user/repo.git\n",
+            " ! [rejected]        main -> main (non-fast-forward)\n",
+            " ! [rejected]        dev -> dev (fetch first)\n",
+            "error: failed to push some refs to 'github.com:user/repo.git'\n",
+        ));
+
+        match error {
+            GitError::NonFastForward { refs, .. } => assert_eq!(vec!["main", "dev"], refs),
+            e => panic!("Expected NonFastForward, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_authentication_failed() {
+        let error = classify_stderr(
+            "fatal: Authentication failed for 'https://github.com/user/repo.git/'\n",
+        );
+
+        match error {
+            GitError::AuthenticationFailed { url, .. } => {
+                assert_eq!(Some(String::from("https://github.com/user/repo.git/")), url)
+            }
+            e => panic!("Expected AuthenticationFailed, got {:?}", e),
+        }
+
+        let error = classify_stderr("git@github.com: Permission denied (publickey).\n");
+        assert!(matches!(
+            error,
+            GitError::AuthenticationFailed { url: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_ref_not_found() {
+        let stderrs = vec![
+            "fatal: ambiguous argument 'nope': unknown revision or path not in the working tree.\n",
+            "fatal: couldn't find remote ref nope\n",
+            "error: branch 'nope' not found.\n",
+        ];
+
+        for stderr in stderrs {
+            match classify_stderr(stderr) {
+                GitError::RefNotFound { reference, .. } => assert_eq!("nope", reference),
+                e => panic!("Expected RefNotFound, got {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pathspec_not_matched() {
+        let error =
+            classify_stderr("error: pathspec 'nope' did not match any file(s) known to git\n");
+
+        match error {
+            GitError::PathspecNotMatched { pathspecs, .. } => assert_eq!(vec!["nope"], pathspecs),
+            e => panic!("Expected PathspecNotMatched, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_nothing_to_commit() {
+        let error = classify(
+            String::from("git commit"),
+            Some(1),
+            String::from("On branch master\nnothing to commit, working tree clean\n"),
+            String::new(),
+        );
+
+        assert!(matches!(error, GitError::NothingToCommit { .. }));
+    }
 }
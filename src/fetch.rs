@@ -0,0 +1,338 @@
+use super::GitError;
+use crate::types::Result;
+
+///Whether tags are fetched along with branches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tags {
+    ///Tags pointing at fetched commits
+    Default,
+    All,
+    None,
+}
+
+impl Tags {
+    pub(crate) fn arg(self) -> Option<&'static str> {
+        match self {
+            Tags::Default => None,
+            Tags::All => Some("--tags"),
+            Tags::None => Some("--no-tags"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum History {
+    Depth(u32),
+    Deepen(u32),
+    Unshallow,
+}
+
+///Options for `Repository::fetch`
+///
+///By default the current branch's remote, or `origin`, is fetched using its configured
+///refspecs. Requires git 2.41 or later for `--porcelain` output.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    remote: Option<String>,
+    all: bool,
+    refspecs: Vec<String>,
+    prune: bool,
+    prune_tags: bool,
+    tags: Tags,
+    depth: Option<History>,
+    filter: Option<String>,
+    atomic: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> FetchOptions {
+        FetchOptions {
+            remote: None,
+            all: false,
+            refspecs: Vec::new(),
+            prune: false,
+            prune_tags: false,
+            tags: Tags::Default,
+            depth: None,
+            filter: None,
+            atomic: false,
+        }
+    }
+}
+
+impl FetchOptions {
+    pub fn new() -> FetchOptions {
+        FetchOptions::default()
+    }
+
+    pub fn remote(mut self, remote: &str) -> Self {
+        self.remote = Some(remote.to_owned());
+        self
+    }
+
+    ///Fetch every remote, ignoring any remote or refspecs given
+    pub fn all(mut self) -> Self {
+        self.all = true;
+        self
+    }
+
+    ///Fetch the given refspec, such as `+refs/heads/main:refs/remotes/origin/main`, instead of
+    ///the remote's configured ones, from `origin` unless another remote is set
+    pub fn refspec(mut self, refspec: &str) -> Self {
+        self.refspecs.push(refspec.to_owned());
+        self
+    }
+
+    ///Delete remote-tracking branches which no longer exist on the remote
+    pub fn prune(mut self) -> Self {
+        self.prune = true;
+        self
+    }
+
+    ///Delete local tags which no longer exist on the remote, as well as pruning branches
+    pub fn prune_tags(mut self) -> Self {
+        self.prune = true;
+        self.prune_tags = true;
+        self
+    }
+
+    ///Fetch all tags from the remote as well
+    pub fn tags(mut self) -> Self {
+        self.tags = Tags::All;
+        self
+    }
+
+    ///Don't fetch any tags, even those pointing at fetched commits
+    pub fn no_tags(mut self) -> Self {
+        self.tags = Tags::None;
+        self
+    }
+
+    ///Truncate history to the given number of commits from each fetched tip
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(History::Depth(depth));
+        self
+    }
+
+    ///Extend the history of a shallow repository by the given number of commits
+    pub fn deepen(mut self, depth: u32) -> Self {
+        self.depth = Some(History::Deepen(depth));
+        self
+    }
+
+    ///Fetch the complete history of a shallow repository
+    pub fn unshallow(mut self) -> Self {
+        self.depth = Some(History::Unshallow);
+        self
+    }
+
+    ///Omit objects from a partial clone using the given filter, such as `blob:none`
+    pub fn filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_owned());
+        self
+    }
+
+    ///Update either all refs or none of them
+    pub fn atomic(mut self) -> Self {
+        self.atomic = true;
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("fetch"), String::from("--porcelain")];
+        if self.prune {
+            args.push(String::from("--prune"));
+        }
+        if self.prune_tags {
+            args.push(String::from("--prune-tags"));
+        }
+        args.extend(self.tags.arg().map(String::from));
+        match self.depth {
+            Some(History::Depth(depth)) => args.push(format!("--depth={}", depth)),
+            Some(History::Deepen(depth)) => args.push(format!("--deepen={}", depth)),
+            Some(History::Unshallow) => args.push(String::from("--unshallow")),
+            None => {}
+        }
+        if let Some(filter) = &self.filter {
+            args.push(format!("--filter={}", filter));
+        }
+        if self.atomic {
+            args.push(String::from("--atomic"));
+        }
+        if self.all {
+            args.push(String::from("--all"));
+        } else if self.remote.is_some() || !self.refspecs.is_empty() {
+            args.push(String::from("--end-of-options"));
+            args.push(
+                self.remote
+                    .clone()
+                    .unwrap_or_else(|| String::from("origin")),
+            );
+            args.extend(self.refspecs.iter().cloned());
+        }
+        args
+    }
+}
+
+///How a ref was changed by `Repository::fetch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateFlag {
+    FastForward,
+    ///The ref was updated to a commit which doesn't contain its old value
+    Forced,
+    ///The ref was pruned
+    Deleted,
+    ///A tag was moved to a different object
+    TagUpdate,
+    New,
+    ///The ref couldn't be updated, such as a non fast-forward without a `+` refspec
+    Rejected,
+    UpToDate,
+}
+
+///A ref changed by `Repository::fetch`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefUpdate {
+    ///The full name of the local ref, such as `refs/remotes/origin/main`
+    pub reference: String,
+    ///The object the ref pointed at before, `None` if it didn't exist
+    pub old: Option<String>,
+    ///The object the ref points at now, `None` if it was deleted
+    pub new: Option<String>,
+    pub flag: UpdateFlag,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+fn object(hash: &str) -> Option<String> {
+    Some(hash.to_owned()).filter(|h| h.chars().any(|c| c != '0'))
+}
+
+///Parse the output of `git fetch --porcelain`, where each line is a flag, the old and new
+///objects and the local ref, separated by spaces
+pub(crate) fn parse_updates(output: &str) -> Result<Vec<RefUpdate>> {
+    output
+        .lines()
+        .map(|line| {
+            let flag = match line.chars().next() {
+                Some(' ') => UpdateFlag::FastForward,
+                Some('+') => UpdateFlag::Forced,
+                Some('-') => UpdateFlag::Deleted,
+                Some('t') => UpdateFlag::TagUpdate,
+                Some('*') => UpdateFlag::New,
+                Some('!') => UpdateFlag::Rejected,
+                Some('=') => UpdateFlag::UpToDate,
+                _ => return Err(unexpected(line)),
+            };
+            let fields: Vec<&str> = line
+                .get(2..)
+                .ok_or_else(|| unexpected(line))?
+                .splitn(3, ' ')
+                .collect();
+            if fields.len() != 3 {
+                return Err(unexpected(line));
+            }
+            Ok(RefUpdate {
+                reference: fields[2].to_owned(),
+                old: object(fields[0]),
+                new: object(fields[1]),
+                flag,
+            })
+        })
+        .collect()
+}
+
+///The ref updates reported by a fetch which failed because some refs were rejected, or the
+///error if it failed for any other reason
+pub(crate) fn rejected_updates(error: GitError) -> Result<Vec<RefUpdate>> {
+    if let GitError::GitError { stdout, .. } = &error {
+        if let Ok(updates) = parse_updates(stdout) {
+            if updates.iter().any(|u| u.flag == UpdateFlag::Rejected) {
+                return Ok(updates);
+            }
+        }
+    }
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let options = FetchOptions::new()
+            .refspec("+refs/heads/main:refs/remotes/origin/main")
+            .prune_tags()
+            .no_tags()
+            .deepen(5)
+            .filter("blob:none")
+            .atomic();
+
+        assert_eq!(
+            vec![
+                "fetch",
+                "--porcelain",
+                "--prune",
+                "--prune-tags",
+                "--no-tags",
+                "--deepen=5",
+                "--filter=blob:none",
+                "--atomic",
+                "--end-of-options",
+                "origin",
+                "+refs/heads/main:refs/remotes/origin/main"
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec!["fetch", "--porcelain", "--tags", "--unshallow", "--all"],
+            FetchOptions::new()
+                .remote("upstream")
+                .tags()
+                .depth(1)
+                .unshallow()
+                .all()
+                .args()
+        );
+        assert_eq!(vec!["fetch", "--porcelain"], FetchOptions::new().args());
+    }
+
+    #[test]
+    fn test_parse_updates() {
+        let zero = "0".repeat(40);
+        let a = "a".repeat(40);
+        let b = "b".repeat(40);
+        let output = format!(
+            "  {a} {b} refs/remotes/origin/main\n+ {b} {a} refs/remotes/origin/rewritten\n\
+             * {zero} {a} refs/tags/v1.0\n- {b} {zero} refs/remotes/origin/gone\n\
+             ! {a} {b} refs/heads/checked-out\n",
+            a = a,
+            b = b,
+            zero = zero
+        );
+
+        let updates = parse_updates(&output).unwrap();
+
+        assert_eq!(
+            RefUpdate {
+                reference: String::from("refs/remotes/origin/main"),
+                old: Some(a.clone()),
+                new: Some(b.clone()),
+                flag: UpdateFlag::FastForward,
+            },
+            updates[0]
+        );
+        assert_eq!(UpdateFlag::Forced, updates[1].flag);
+        assert_eq!(None, updates[2].old);
+        assert_eq!(UpdateFlag::New, updates[2].flag);
+        assert_eq!(None, updates[3].new);
+        assert_eq!(UpdateFlag::Deleted, updates[3].flag);
+        assert_eq!(UpdateFlag::Rejected, updates[4].flag);
+        assert!(parse_updates("").unwrap().is_empty());
+        assert!(parse_updates("? aaaa bbbb refs/heads/main\n").is_err());
+        assert!(parse_updates("* aaaa refs/heads/main\n").is_err());
+    }
+}
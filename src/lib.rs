@@ -1,65 +1,261 @@
+use branch::Branch;
+use builder::{RepositoryBuilder, Settings};
+use clone::CloneOptions;
+use commit::CommitBuilder;
+use config::{ConfigEntry, ConfigScope};
 use error::GitError;
+use fetch::{FetchOptions, RefUpdate};
+use merge::{MergeOptions, MergeOutcome};
+use metadata::ObjectFormat;
+use progress::{ProgressParser, ProgressSink};
+use pull::{PullOptions, PullOutcome};
+use push::{PushOptions, PushResult};
+use rebase::{RebaseOptions, RebaseOutcome, RebaseState};
+use remote::{Remote, RemoteHead};
+use reset::ResetMode;
+use runner::{CancellationToken, GitOutput, GitRunner, Interruption, Invocation, ProcessRunner};
+use stash::{StashApplyOutcome, StashEntry, StashOptions};
+use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use diff::{Diff, DiffSpec, DiffStat};
+use log::{Commit, LogOptions};
 use std::str;
-use types::{BranchName, GitUrl, Result};
+use status::Status;
+use std::sync::Arc;
+use std::time::Duration;
+use submodule::{Submodule, SubmoduleUpdateOptions};
+use tag::{ListTagsOptions, Tag, TagOptions};
+use types::{BranchName, GitUrl, Result, TagName};
+use worktree::{Worktree, WorktreeOptions};
 
+#[cfg(feature = "tokio")]
+mod async_repository;
+pub mod branch;
+pub mod builder;
+pub mod clone;
+pub mod commit;
+pub mod config;
+pub mod diff;
 pub mod error;
+pub mod fetch;
+pub mod log;
+pub mod merge;
+pub mod metadata;
+pub mod progress;
+pub mod pull;
+pub mod push;
+pub mod rebase;
+pub mod remote;
+pub mod reset;
+pub mod runner;
+pub mod stash;
+pub mod status;
+pub mod submodule;
+pub mod tag;
 pub mod types;
+pub mod worktree;
+
+#[cfg(feature = "tokio")]
+pub use async_repository::AsyncRepository;
 
 ///A local git repository
 pub struct Repository {
     location: PathBuf,
+    bare: bool,
+    runner: Arc<dyn GitRunner>,
+    settings: Settings,
 }
 
 impl Repository {
     ///Create a Repository struct from a pre-existing local git repository
     pub fn new<P: AsRef<Path>>(p: P) -> Repository {
+        Repository::with_runner(p, Arc::new(ProcessRunner))
+    }
+
+    ///Create a Repository struct which executes git through the given runner
+    pub fn with_runner<P: AsRef<Path>>(p: P, runner: Arc<dyn GitRunner>) -> Repository {
         let p = p.as_ref();
         Repository {
             location: PathBuf::from(p),
+            bare: false,
+            runner,
+            settings: Settings::default(),
+        }
+    }
+
+    ///Find the repository containing the given folder, which may be anywhere within its working
+    ///tree or git directory
+    pub fn discover<P: AsRef<Path>>(p: P) -> Result<Repository> {
+        RepositoryBuilder::new(p).discover()
+    }
+
+    ///Open the repository at the given folder, which must be the root of its working tree or its
+    ///git directory, failing with `GitError::NotARepository` otherwise
+    pub fn open<P: AsRef<Path>>(p: P) -> Result<Repository> {
+        RepositoryBuilder::new(p).open()
+    }
+
+    ///Configure the git executable, environment and global options used by a Repository
+    pub fn builder<P: AsRef<Path>>(p: P) -> RepositoryBuilder {
+        RepositoryBuilder::new(p)
+    }
+
+    ///A handle to the same repository whose commands are killed if they run for longer than
+    ///the given duration, returning `GitError::Timeout`
+    ///
+    ///```no_run
+    ///# use rustygit::Repository;
+    ///# use std::time::Duration;
+    ///let repo = Repository::new(".");
+    ///repo.with_timeout(Duration::from_secs(30)).push().unwrap();
+    ///```
+    pub fn with_timeout(&self, timeout: Duration) -> Repository {
+        let mut repo = self.handle();
+        repo.settings.timeout = Some(timeout);
+        repo
+    }
+
+    ///A handle to the same repository whose commands are killed once the given token is
+    ///cancelled, returning `GitError::Cancelled`
+    pub fn with_cancellation(&self, token: &CancellationToken) -> Repository {
+        let mut repo = self.handle();
+        repo.settings.cancellation = Some(token.clone());
+        repo
+    }
+
+    ///A handle to another repository found from this one, such as a linked worktree, run in the
+    ///same way as this one
+    fn related(&self, location: PathBuf) -> Repository {
+        Repository {
+            location,
+            bare: false,
+            runner: self.runner.clone(),
+            settings: self.settings.related(),
+        }
+    }
+
+    fn handle(&self) -> Repository {
+        Repository {
+            location: self.location.clone(),
+            bare: self.bare,
+            runner: self.runner.clone(),
+            settings: self.settings.clone(),
         }
     }
 
     ///Clone a remote git repository locally
     pub fn clone<P: AsRef<Path>>(url: GitUrl, p: P) -> Result<Repository> {
-        let p = p.as_ref();
+        Repository::clone_with(&url, p, &CloneOptions::new())
+    }
+
+    ///Clone a remote git repository locally with the given options
+    pub fn clone_with<P: AsRef<Path>>(
+        url: &GitUrl,
+        p: P,
+        options: &CloneOptions,
+    ) -> Result<Repository> {
+        let mut repo = Repository::new(p);
+        repo.clone_into(url, options, None)?;
+        Ok(repo)
+    }
+
+    ///Clone a remote git repository locally, reporting progress as it runs
+    pub fn clone_with_progress<P: AsRef<Path>, S: ProgressSink>(
+        url: &GitUrl,
+        p: P,
+        options: &CloneOptions,
+        mut progress: S,
+    ) -> Result<Repository> {
+        let mut repo = Repository::new(p);
+        repo.clone_into(url, options, Some(&mut progress))?;
+        Ok(repo)
+    }
 
+    pub(crate) fn clone_into(
+        &mut self,
+        url: &GitUrl,
+        options: &CloneOptions,
+        progress: Option<&mut dyn ProgressSink>,
+    ) -> Result<()> {
+        self.bare = options.is_bare();
         let cwd = env::current_dir().map_err(|_| GitError::WorkingDirectoryInaccessible)?;
-        execute_git(cwd, &["clone", url.value.as_str(), p.to_str().unwrap()]).map(|_| Repository {
-            location: PathBuf::from(p),
-        })
+        let mut args = options.args(url, &self.location);
+        match progress {
+            Some(progress) => {
+                args.insert(1, "--progress".into());
+                let invocation = self.settings.invocation(cwd, args);
+                self.run_with_progress(invocation, progress, |_| Ok(()))
+            }
+            None => self.run(self.settings.invocation(cwd, args), |_| Ok(())),
+        }
     }
 
     ///Initialise a given folder as a git repository
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn init<P: AsRef<Path>>(p: P) -> Result<Repository> {
-        let p = p.as_ref();
-        execute_git(&p, &["init"])?;
-        Ok(Repository {
-            location: PathBuf::from(p),
-        })
+        let repo = Repository::new(p);
+        repo.execute_git(&["init"])?;
+        Ok(repo)
+    }
+
+    ///Returns true if this repository has no working tree
+    pub fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    ///The root of the working tree, `None` for a bare repository or a handle to a git directory
+    pub fn workdir(&self) -> Result<Option<PathBuf>> {
+        let discovery = self.execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)?;
+        if !discovery.inside_work_tree {
+            return Ok(None);
+        }
+        let toplevel = self.execute_git_fn(metadata::TOPLEVEL_ARGS, parse_trimmed)?;
+        Ok(Some(PathBuf::from(toplevel)))
+    }
+
+    ///The absolute path of the git directory, which is within the main repository's for a
+    ///linked worktree
+    pub fn git_dir(&self) -> Result<PathBuf> {
+        let discovery = self.execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)?;
+        Ok(discovery.git_dir)
+    }
+
+    ///Returns true if the repository was cloned with truncated history
+    pub fn is_shallow(&self) -> Result<bool> {
+        self.execute_git_fn(metadata::SHALLOW_ARGS, metadata::parse_bool)
+    }
+
+    ///Returns true if this is a linked worktree rather than the main working tree
+    pub fn is_worktree(&self) -> Result<bool> {
+        let discovery = self.execute_git_fn(metadata::DISCOVER_ARGS, metadata::parse_discovery)?;
+        Ok(discovery.git_dir != discovery.common_dir)
+    }
+
+    ///The hash algorithm used to name objects
+    pub fn object_format(&self) -> Result<ObjectFormat> {
+        self.execute_git_fn(metadata::OBJECT_FORMAT_ARGS, metadata::parse_object_format)
     }
 
     ///Create and checkout a new local branch
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn create_local_branch(&self, branch_name: &BranchName) -> Result<()> {
-        execute_git(
-            &self.location,
-            &["checkout", "-b", branch_name.value.as_str()],
-        )
+        self.execute_git(&["checkout", "-b", branch_name.value.as_str()])
     }
 
     ///Checkout the specified branch
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn switch_branch(&self, branch_name: &BranchName) -> Result<()> {
-        execute_git(&self.location, &["checkout", branch_name.value.as_str()])
+        self.execute_git(&["checkout", branch_name.value.as_str()])
     }
 
     ///Add file contents to the index
     pub fn add(&self, pathspecs: Vec<&str>) -> Result<()> {
         let mut args = pathspecs.clone();
         args.insert(0, "add");
-        execute_git(&self.location, args)
+        self.execute_git(args)
     }
 
     ///Remove file contents from the index
@@ -69,100 +265,330 @@ impl Repository {
         if force {
             args.push("-f");
         }
-        execute_git(&self.location, args)
+        self.execute_git(args)
+    }
+
+    ///Move the current branch to the target commit, updating the index and working tree as
+    ///the mode requires
+    pub fn reset(&self, mode: ResetMode, target: &str) -> Result<()> {
+        self.execute_git(reset::reset_args(mode, target))
+    }
+
+    ///Restore paths in the working tree, the index or both from `source`, or from the index if
+    ///not given
+    ///
+    ///Only the working tree is restored when neither `staged` nor `worktree` is given.
+    pub fn restore(
+        &self,
+        pathspecs: Vec<&str>,
+        source: Option<&str>,
+        staged: bool,
+        worktree: bool,
+    ) -> Result<()> {
+        self.execute_git(reset::restore_args(pathspecs, source, staged, worktree))
+    }
+
+    ///Replace paths in the index and working tree with their contents in `tree_ish`, or in
+    ///the index if not given
+    pub fn checkout_paths(&self, tree_ish: Option<&str>, pathspecs: Vec<&str>) -> Result<()> {
+        self.execute_git(reset::checkout_paths_args(tree_ish, pathspecs))
+    }
+
+    ///Discard all changes to tracked files and remove untracked files, keeping ignored ones
+    ///
+    ///On a branch with no commits yet, staged files are removed too. Untracked directories
+    ///containing a git repository, such as a nested clone, are left in place.
+    pub fn discard_all_changes(&self) -> Result<()> {
+        self.execute_git(reset::HARD_RESET_ARGS)?;
+        self.execute_git(reset::CLEAN_ARGS)
     }
     ///Commit all staged files
     pub fn commit_all(&self, message: &str) -> Result<()> {
-        execute_git(&self.location, &["commit", "-am", message])
+        self.commit(&CommitBuilder::new(message).all()).map(|_| ())
+    }
+
+    ///Create a new commit, returning its hash
+    pub fn commit(&self, builder: &CommitBuilder) -> Result<String> {
+        let mut invocation = self.invocation(builder.args());
+        invocation.envs.extend(builder.envs());
+        invocation.input = builder.input();
+        self.run(invocation, |_| Ok(()))?;
+        self.get_hash(false)
     }
 
     ///Push the curent branch to its associated remote
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn push(&self) -> Result<()> {
-        execute_git(&self.location, &["push"])
+        self.execute_git(&["push"])
+    }
+
+    ///Push the curent branch to its associated remote, reporting progress as it runs
+    pub fn push_with_progress<S: ProgressSink>(&self, mut progress: S) -> Result<()> {
+        let invocation = self.invocation(["push", "--progress"]);
+        self.run_with_progress(invocation, &mut progress, |_| Ok(()))
     }
 
     ///Push the curent branch to its associated remote, specifying the upstream branch
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn push_to_upstream(&self, upstream: &str, upstream_branch: &BranchName) -> Result<()> {
-        execute_git(
-            &self.location,
-            &["push", "-u", upstream, upstream_branch.value.as_str()],
-        )
+        self.execute_git(&["push", "-u", upstream, upstream_branch.value.as_str()])
+    }
+
+    ///Push to a remote with the given options, returning the outcome for each ref
+    ///
+    ///Refs the push was refused for are returned with their reason rather than as an error.
+    pub fn push_with(&self, options: &PushOptions) -> Result<Vec<PushResult>> {
+        match self.execute_git_fn(options.args(), push::parse_results) {
+            Err(error) => push::rejected_results(error),
+            result => result,
+        }
     }
 
     ///Add a new remote
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn add_remote(&self, name: &str, url: &GitUrl) -> Result<()> {
-        execute_git(&self.location, &["remote", "add", name, url.value.as_str()])
+        self.execute_git(&["remote", "add", name, url.value.as_str()])
     }
 
     ///Fetch a remote
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn fetch_remote(&self, remote: &str) -> Result<()> {
-        execute_git(&self.location, &["fetch", remote])
+        self.execute_git(&["fetch", remote])
+    }
+
+    ///Fetch a remote, reporting progress as it runs
+    pub fn fetch_remote_with_progress<S: ProgressSink>(
+        &self,
+        remote: &str,
+        mut progress: S,
+    ) -> Result<()> {
+        let invocation = self.invocation(["fetch", "--progress", remote]);
+        self.run_with_progress(invocation, &mut progress, |_| Ok(()))
+    }
+
+    ///Fetch from a remote, returning the local refs which were updated
+    ///
+    ///Refs which couldn't be updated are returned flagged as rejected rather than as an error.
+    ///Requires git 2.41 or later, which added `fetch --porcelain`.
+    pub fn fetch(&self, options: &FetchOptions) -> Result<Vec<RefUpdate>> {
+        match self.execute_git_fn(options.args(), fetch::parse_updates) {
+            Err(error) => fetch::rejected_updates(error),
+            result => result,
+        }
     }
 
     ///Create a new branch from a start point, such as another local or remote branch
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn create_branch_from_startpoint(
         &self,
         branch_name: &BranchName,
         startpoint: &str,
     ) -> Result<()> {
-        execute_git(
-            &self.location,
-            &[
-                "checkout",
-                "-b",
-                branch_name.to_string().as_str(),
-                startpoint,
-            ],
-        )
+        self.execute_git(&[
+            "checkout",
+            "-b",
+            branch_name.to_string().as_str(),
+            startpoint,
+        ])
     }
 
     ///List local branches
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn list_branches(&self) -> Result<Vec<String>> {
-        execute_git_fn(
-            &self.location,
-            &["branch", "--format=%(refname:short)"],
-            |output| Ok(output.lines().map(|line| line.to_owned()).collect()),
-        )
+        self.execute_git_fn(&["branch", "--format=%(refname:short)"], parse_lines)
+    }
+
+    ///Rename a local branch
+    pub fn rename_branch(&self, from: &BranchName, to: &BranchName) -> Result<()> {
+        self.execute_git(["branch", "--move", from.value.as_str(), to.value.as_str()])
+    }
+
+    ///Delete a local branch, which unless forced must be merged into its upstream or `HEAD`
+    pub fn delete_branch(&self, branch_name: &BranchName, force: bool) -> Result<()> {
+        let mut args = vec!["branch", "--delete"];
+        if force {
+            args.push("--force");
+        }
+        args.push(branch_name.value.as_str());
+        self.execute_git(args)
+    }
+
+    ///Set the upstream of a local branch, such as `origin/main`
+    pub fn set_upstream(&self, branch_name: &BranchName, upstream: &str) -> Result<()> {
+        self.execute_git([
+            "branch",
+            format!("--set-upstream-to={}", upstream).as_str(),
+            branch_name.value.as_str(),
+        ])
+    }
+
+    ///Remove the upstream of a local branch
+    pub fn unset_upstream(&self, branch_name: &BranchName) -> Result<()> {
+        self.execute_git(["branch", "--unset-upstream", branch_name.value.as_str()])
+    }
+
+    ///The name of the checked out branch, `None` if `HEAD` is detached
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        self.execute_git_fn(["branch", "--show-current"], parse_optional)
+    }
+
+    ///List local and remote-tracking branches along with their upstreams and tips
+    pub fn list_branches_detailed(&self) -> Result<Vec<Branch>> {
+        self.execute_git_fn(branch::list_args(), branch::parse_branches)
+    }
+
+    ///Get the status of the working tree and index
+    pub fn status(&self) -> Result<Status> {
+        self.execute_git_fn(status::STATUS_ARGS, status::parse_status)
+    }
+
+    ///Get the status of the working tree and index, including ignored files
+    pub fn status_with_ignored(&self) -> Result<Status> {
+        self.execute_git_fn(status::IGNORED_STATUS_ARGS, status::parse_status)
     }
 
     ///List files added to staging area
     pub fn list_added(&self) -> Result<Vec<String>> {
-        git_status(&self, "A")
+        Ok(self.status()?.added())
     }
 
-    ///List all modified files
+    ///List all modified files, whether or not the modifications are staged
     pub fn list_modified(&self) -> Result<Vec<String>> {
-        git_status(&self, " M")
+        Ok(self.status()?.modified())
     }
 
     ///List all untracked files
     pub fn list_untracked(&self) -> Result<Vec<String>> {
-        git_status(&self, "??")
+        Ok(self.status()?.untracked)
     }
 
     ///List tracked files
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn list_tracked(&self) -> Result<Vec<String>> {
-        execute_git_fn(&self.location, &["ls-files"], |output| {
-            Ok(output.lines().map(|line| line.to_owned()).collect())
-        })
+        self.execute_git_fn(&["ls-files"], parse_lines)
     }
 
     ///List all the remote URI for name
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn show_remote_uri(&self, remote_name: &str) -> Result<String> {
-        execute_git_fn(&self.location, &["config", "--get", format!("remote.{}.url", remote_name).as_str()], |output| {
-            Ok(output.trim().to_owned())
-        })
+        self.execute_git_fn(
+            &[
+                "config",
+                "--get",
+                format!("remote.{}.url", remote_name).as_str(),
+            ],
+            parse_trimmed,
+        )
+    }
+
+    ///The value of a config key, `None` if it isn't set
+    ///
+    ///Without a scope the value that applies to this repository is returned, which for a key
+    ///set several times is the last.
+    pub fn config_get(&self, key: &str, scope: Option<&ConfigScope>) -> Result<Option<String>> {
+        Ok(self
+            .config_values(config::get_args(scope, None, false, key))?
+            .pop())
+    }
+
+    ///Every value of a multi-valued config key, in the order git reads them
+    pub fn config_get_all(&self, key: &str, scope: Option<&ConfigScope>) -> Result<Vec<String>> {
+        self.config_values(config::get_args(scope, None, true, key))
+    }
+
+    ///The value of a config key interpreted by git as a boolean, such as `yes` or `off`
+    pub fn config_get_bool(&self, key: &str, scope: Option<&ConfigScope>) -> Result<Option<bool>> {
+        self.config_values(config::get_args(scope, Some("bool"), false, key))?
+            .pop()
+            .map(|value| config::parse_bool(&value))
+            .transpose()
+    }
+
+    ///The value of a config key interpreted by git as an integer, expanding suffixes such as `k`
+    pub fn config_get_int(&self, key: &str, scope: Option<&ConfigScope>) -> Result<Option<i64>> {
+        self.config_values(config::get_args(scope, Some("int"), false, key))?
+            .pop()
+            .map(|value| config::parse_int(&value))
+            .transpose()
+    }
+
+    ///The value of a config key interpreted by git as a path, expanding a leading `~`
+    pub fn config_get_path(
+        &self,
+        key: &str,
+        scope: Option<&ConfigScope>,
+    ) -> Result<Option<PathBuf>> {
+        Ok(self
+            .config_values(config::get_args(scope, Some("path"), false, key))?
+            .pop()
+            .map(PathBuf::from))
+    }
+
+    ///Set a config key, replacing any values it already has in the given scope
+    pub fn config_set(&self, key: &str, value: &str, scope: &ConfigScope) -> Result<()> {
+        self.execute_git(config::set_args(scope, key, value, false))
+    }
+
+    ///Add a value to a multi-valued config key, keeping the values it already has
+    pub fn config_add(&self, key: &str, value: &str, scope: &ConfigScope) -> Result<()> {
+        self.execute_git(config::set_args(scope, key, value, true))
+    }
+
+    ///Remove every value of a config key in the given scope, succeeding if it isn't set
+    pub fn config_unset(&self, key: &str, scope: &ConfigScope) -> Result<()> {
+        match self.execute_git(config::unset_args(scope, key)) {
+            Err(error) if error.exit_code() == Some(config::NOTHING_TO_UNSET_EXIT_CODE) => Ok(()),
+            result => result,
+        }
+    }
+
+    ///List config values with where each was read from, across all scopes unless one is given
+    pub fn config_list(&self, scope: Option<&ConfigScope>) -> Result<Vec<ConfigEntry>> {
+        self.execute_git_fn(config::list_args(scope), config::parse_entries)
     }
 
     ///List all the remote URI for name
+    #[allow(clippy::needless_borrows_for_generic_args)]
     pub fn list_remotes(&self) -> Result<Vec<String>> {
-        execute_git_fn(&self.location, &["remote", "show"], |output| {
-            if output.trim().is_empty() {
-                Err(GitError::NoRemoteRepositorySet)
-            } else {
-                Ok(output.lines().map(|line| line.to_owned()).collect())
-            }
-        })
+        self.execute_git_fn(&["remote", "show"], parse_remotes)
+    }
+
+    ///List remotes with their URLs and default refspecs, empty if there are none
+    pub fn remotes(&self) -> Result<Vec<Remote>> {
+        let mut remotes = self.execute_git_fn(remote::VERBOSE_LIST_ARGS, remote::parse_remotes)?;
+        if !remotes.is_empty() {
+            let refspecs = self.config_values(remote::REFSPECS_ARGS)?;
+            remote::apply_refspecs(&mut remotes, &refspecs)?;
+        }
+        Ok(remotes)
+    }
+
+    ///Rename a remote, along with its remote-tracking branches and configuration
+    pub fn rename_remote(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.execute_git(remote::rename_args(old_name, new_name))
+    }
+
+    ///Remove a remote, along with its remote-tracking branches and configuration
+    pub fn remove_remote(&self, name: &str) -> Result<()> {
+        self.execute_git(remote::remove_args(name))
+    }
+
+    ///Replace the URL of a remote, or its push URL if `push` is given
+    pub fn set_remote_url(&self, name: &str, url: &GitUrl, push: bool) -> Result<()> {
+        self.execute_git(remote::set_url_args(name, url.value.as_str(), push))
+    }
+
+    ///Delete remote-tracking branches whose branch no longer exists on the remote, returning
+    ///their names
+    ///
+    ///With `dry_run` the branches which would be deleted are returned and none are.
+    pub fn prune_remote(&self, name: &str, dry_run: bool) -> Result<Vec<String>> {
+        self.execute_git_fn(remote::prune_args(name, dry_run), remote::parse_pruned)
+    }
+
+    ///Set which remote-tracking branch a remote's `HEAD` points at, such as `origin/HEAD`
+    pub fn remote_set_head(&self, name: &str, head: &RemoteHead) -> Result<()> {
+        self.execute_git(remote::set_head_args(name, head))
     }
 
     /// Obtains commit hash of the current `HEAD`.
@@ -172,7 +598,278 @@ impl Repository {
         } else {
             &["rev-parse", "HEAD"]
         };
-        execute_git_fn(&self.location, args, |output| Ok(output.trim().to_owned()))
+        self.execute_git_fn(args, parse_trimmed)
+    }
+
+    ///Merge other branches or commits into the current branch
+    ///
+    ///Conflicts are reported as `MergeOutcome::Conflicted` rather than an error, leaving the
+    ///merge in progress to be resolved and completed with `merge_continue`, or abandoned with
+    ///`merge_abort`.
+    pub fn merge(&self, options: &MergeOptions) -> Result<MergeOutcome> {
+        let before = self.get_hash(false).ok();
+        let targets = self.execute_git_fn(options.target_args(), parse_lines)?;
+
+        if let Err(error) = self.execute_git(options.args()) {
+            let conflicts = self.execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(MergeOutcome::Conflicted { conflicts })
+            };
+        }
+
+        if options.is_squash() {
+            let index = self.execute_git_fn(["write-tree"], parse_trimmed)?;
+            let head = self.execute_git_fn(["rev-parse", "HEAD^{tree}"], parse_trimmed)?;
+            return Ok(if index == head {
+                MergeOutcome::UpToDate
+            } else {
+                MergeOutcome::Squashed
+            });
+        }
+        let after = self.get_hash(false)?;
+        Ok(merge::outcome(before.as_deref(), &after, &targets))
+    }
+
+    ///Commit a merge once its conflicts have been resolved and staged, returning the hash of
+    ///the merge commit
+    pub fn merge_continue(&self) -> Result<String> {
+        let mut invocation = self.invocation(["merge", "--continue"]);
+        invocation.envs.push(("GIT_EDITOR".into(), ":".into()));
+        self.run(invocation, |_| Ok(()))?;
+        self.get_hash(false)
+    }
+
+    ///Abandon a conflicted merge, restoring the state from before it started
+    pub fn merge_abort(&self) -> Result<()> {
+        self.execute_git(["merge", "--abort"])
+    }
+
+    ///Fetch from a remote and integrate the fetched commits into the current branch
+    ///
+    ///Conflicts are reported as `PullOutcome::Conflicted` rather than an error, leaving the merge
+    ///or rebase in progress to be resolved and continued, or aborted.
+    pub fn pull(&self, options: &PullOptions) -> Result<PullOutcome> {
+        let before = self.get_hash(false).ok();
+        if let Err(error) = self.execute_git(options.args()) {
+            let conflicts = self.execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(PullOutcome::Conflicted { conflicts })
+            };
+        }
+        let after = self.get_hash(false)?;
+        let fetched = self.execute_git_fn(pull::FETCHED_ARGS, parse_trimmed)?;
+        Ok(pull::outcome(before, after, &fetched, options.is_rebase()))
+    }
+
+    ///Replay the commits of the current branch on top of another
+    ///
+    ///A rebase stopped by conflicts or a failing `exec` command returns
+    ///`RebaseOutcome::Stopped`, to be resumed with `rebase_continue` or `rebase_skip`, or
+    ///abandoned with `rebase_abort`.
+    pub fn rebase(&self, options: &RebaseOptions) -> Result<RebaseOutcome> {
+        self.run_rebase(options.args())
+    }
+
+    ///Resume a stopped rebase once any conflicts have been resolved and staged
+    pub fn rebase_continue(&self) -> Result<RebaseOutcome> {
+        self.run_rebase(["rebase", "--continue"])
+    }
+
+    ///Resume a stopped rebase, dropping the commit it stopped at
+    pub fn rebase_skip(&self) -> Result<RebaseOutcome> {
+        self.run_rebase(["rebase", "--skip"])
+    }
+
+    ///Abandon a stopped rebase, restoring the branch to where it was before
+    pub fn rebase_abort(&self) -> Result<()> {
+        self.execute_git(["rebase", "--abort"])
+    }
+
+    ///The progress of the rebase in progress, `None` if there isn't one
+    pub fn rebase_state(&self) -> Result<Option<RebaseState>> {
+        for dir in rebase::STATE_DIRS {
+            let path = self.execute_git_fn(["rev-parse", "--git-path", dir], parse_trimmed)?;
+            let path = self.location.join(path);
+            if dir == "rebase-apply" && !path.join(rebase::APPLY_REBASING_MARKER).exists() {
+                continue;
+            }
+            if path.is_dir() {
+                let files: HashMap<&str, String> = rebase::STATE_FILES
+                    .iter()
+                    .filter_map(|name| Some((*name, fs::read_to_string(path.join(name)).ok()?)))
+                    .collect();
+                return rebase::parse_state(|name| files.get(name).cloned()).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    ///Stash local changes, returning the hash of the new stash, or `None` if there were no
+    ///changes to stash
+    pub fn stash_push(&self, options: &StashOptions) -> Result<Option<String>> {
+        let before = self.execute_git_fn(stash::HEAD_ARGS, parse_optional)?;
+        self.execute_git(options.args())?;
+        let after = self.execute_git_fn(stash::HEAD_ARGS, parse_optional)?;
+        Ok(after.filter(|after| before.as_ref() != Some(after)))
+    }
+
+    ///List stashes, most recent first
+    pub fn stash_list(&self) -> Result<Vec<StashEntry>> {
+        self.execute_git_fn(stash::LIST_ARGS, stash::parse_stashes)
+    }
+
+    ///Apply the stash at the given index to the working tree, keeping it in the stash list
+    pub fn stash_apply(&self, index: usize) -> Result<StashApplyOutcome> {
+        self.apply_stash("apply", index)
+    }
+
+    ///Apply the stash at the given index to the working tree and drop it, unless it conflicts
+    pub fn stash_pop(&self, index: usize) -> Result<StashApplyOutcome> {
+        self.apply_stash("pop", index)
+    }
+
+    ///Remove the stash at the given index from the stash list
+    pub fn stash_drop(&self, index: usize) -> Result<()> {
+        self.execute_git([
+            "stash".to_owned(),
+            "drop".to_owned(),
+            stash::reference(index),
+        ])
+    }
+
+    ///The changes recorded in the stash at the given index, relative to the commit it was based on
+    pub fn stash_show(&self, index: usize) -> Result<Diff> {
+        self.execute_git_fn(stash::show_args(index), diff::parse_diff)
+    }
+
+    ///Check out another working tree at the given path, sharing this repository's objects and
+    ///refs, returning a handle to it
+    ///
+    ///A relative path is relative to this repository's location.
+    pub fn add_worktree<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &WorktreeOptions,
+    ) -> Result<Repository> {
+        self.execute_git(options.args(path.as_ref()))?;
+        Ok(self.related(self.location.join(path)))
+    }
+
+    ///List the main working tree followed by any linked worktrees
+    pub fn list_worktrees(&self) -> Result<Vec<Worktree>> {
+        self.execute_git_fn(worktree::LIST_ARGS, worktree::parse_worktrees)
+    }
+
+    ///Delete a linked worktree, forcing removes it even if it has local changes
+    pub fn remove_worktree<P: AsRef<Path>>(&self, path: P, force: bool) -> Result<()> {
+        self.execute_git(worktree::remove_args(path.as_ref(), force))
+    }
+
+    ///Stop a linked worktree from being pruned, moved or removed, with an optional reason
+    pub fn lock_worktree<P: AsRef<Path>>(&self, path: P, reason: Option<&str>) -> Result<()> {
+        self.execute_git(worktree::lock_args(path.as_ref(), reason))
+    }
+
+    ///Unlock a linked worktree so it can be pruned, moved or removed again
+    pub fn unlock_worktree<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.execute_git(worktree::unlock_args(path.as_ref()))
+    }
+
+    ///Clean up the administrative files of worktrees which have been deleted
+    pub fn prune_worktrees(&self) -> Result<()> {
+        self.execute_git(["worktree", "prune"])
+    }
+
+    ///The submodules declared in `.gitmodules`, with the commit recorded for each and the state
+    ///of its checkout
+    pub fn submodule_status(&self) -> Result<Vec<Submodule>> {
+        if !self.location.join(submodule::GITMODULES).exists() {
+            return Ok(Vec::new());
+        }
+        let mut submodules =
+            self.execute_git_fn(submodule::CONFIG_ARGS, submodule::parse_gitmodules)?;
+        let gitlinks = self.execute_git_fn(submodule::GITLINKS_ARGS, submodule::parse_gitlinks)?;
+        let status = self.execute_git_fn(submodule::STATUS_ARGS, |o| Ok(o.to_owned()))?;
+        submodule::apply_status(&mut submodules, &gitlinks, &status)?;
+        Ok(submodules)
+    }
+
+    ///Clone, initialise or update submodules as given by the options
+    pub fn submodule_update(&self, options: &SubmoduleUpdateOptions) -> Result<()> {
+        self.execute_git(options.args())
+    }
+
+    ///Add the repository at the given URL as a submodule at the given path, optionally following
+    ///a branch, returning a handle to it
+    pub fn submodule_add<P: AsRef<Path>>(
+        &self,
+        url: &str,
+        path: P,
+        branch: Option<&str>,
+    ) -> Result<Repository> {
+        self.execute_git(submodule::add_args(url, path.as_ref(), branch))?;
+        Ok(self.submodule(path))
+    }
+
+    ///Update the URL of each submodule's remote to match `.gitmodules`
+    pub fn submodule_sync(&self, recursive: bool) -> Result<()> {
+        self.execute_git(submodule::sync_args(recursive))
+    }
+
+    ///Run the given shell command in each checked out submodule, failing if it fails in any
+    pub fn submodule_foreach(&self, command: &str, recursive: bool) -> Result<()> {
+        self.execute_git(submodule::foreach_args(command, recursive))
+    }
+
+    ///A handle to the submodule at the given path, run in the same way as this repository
+    pub fn submodule<P: AsRef<Path>>(&self, path: P) -> Repository {
+        self.related(self.location.join(path))
+    }
+
+    ///List commits matching the given options, most recent first
+    pub fn log(&self, options: &LogOptions) -> Result<Vec<Commit>> {
+        self.execute_git_fn(options.args(), log::parse_log)
+    }
+
+    ///Show changes between the working tree, the index and commits
+    pub fn diff(&self, spec: &DiffSpec) -> Result<Diff> {
+        self.execute_git_fn(spec.patch_args(), diff::parse_diff)
+    }
+
+    ///Summarise the number of lines changed per file, as with `git diff --numstat`
+    pub fn diff_stat(&self, spec: &DiffSpec) -> Result<DiffStat> {
+        self.execute_git_fn(spec.numstat_args(), diff::parse_numstat)
+    }
+
+    ///Create a tag, lightweight unless a message is given or the tag is signed
+    pub fn create_tag(&self, name: &TagName, options: &TagOptions) -> Result<()> {
+        let mut invocation = self.invocation(options.args(name));
+        invocation.input = options.input();
+        self.run(invocation, |_| Ok(()))
+    }
+
+    ///List tags matching the given options
+    pub fn list_tags(&self, options: &ListTagsOptions) -> Result<Vec<Tag>> {
+        self.execute_git_fn(options.args(), tag::parse_tags)
+    }
+
+    ///Delete a local tag
+    pub fn delete_tag(&self, name: &TagName) -> Result<()> {
+        self.execute_git(["tag", "--delete", name.value.as_str()])
+    }
+
+    ///Check the signature of a signed tag, failing if it is unsigned or the signature is bad
+    pub fn verify_tag(&self, name: &TagName) -> Result<()> {
+        self.execute_git(["verify-tag", name.value.as_str()])
+    }
+
+    ///Push all local tags to the given remote
+    pub fn push_tags(&self, remote: &str) -> Result<()> {
+        self.execute_git(["push", "--tags", remote])
     }
 
     /// Execute user defined command
@@ -181,7 +878,7 @@ impl Repository {
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
     {
-        execute_git(&self.location, args)
+        self.execute_git(args)
     }
 
     /// Execute user defined command and return its output
@@ -190,60 +887,201 @@ impl Repository {
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
     {
-        execute_git_fn(&self.location, args, |output| {
-            Ok(output.lines().map(|line| line.to_owned()).collect())
-        })
+        self.execute_git_fn(args, parse_lines)
     }
-}
 
-fn git_status(repo: &Repository, prefix: &str) -> Result<Vec<String>> {
-    execute_git_fn(&repo.location, &["status", "-s"], |output| {
-        Ok(output
-            .lines()
-            .filter(|line| line.starts_with(&prefix))
-            .map(|line| line[3..].to_owned())
-            .collect())
-    })
-}
+    fn invocation<I, S>(&self, args: I) -> Invocation
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.settings.invocation(&self.location, args)
+    }
 
-fn execute_git<I, S, P>(p: P, args: I) -> Result<()>
-where
-    I: IntoIterator<Item = S>,
-    S: AsRef<OsStr>,
-    P: AsRef<Path>,
-{
-    execute_git_fn(p, args, |_| Ok(()))
+    ///Move this handle to the root of the repository found from its location, stopping the
+    ///search at `ceiling` if given
+    pub(crate) fn locate(&mut self, ceiling: Option<PathBuf>) -> Result<()> {
+        let mut invocation = self.invocation(metadata::DISCOVER_ARGS);
+        invocation.envs.extend(
+            ceiling.map(|ceiling| ("GIT_CEILING_DIRECTORIES".into(), ceiling.into_os_string())),
+        );
+        let discovery = self.run(invocation, metadata::parse_discovery)?;
+        self.location = if discovery.inside_work_tree {
+            PathBuf::from(self.execute_git_fn(metadata::TOPLEVEL_ARGS, parse_trimmed)?)
+        } else {
+            discovery.git_dir
+        };
+        self.bare = discovery.bare;
+        Ok(())
+    }
+
+    fn config_values<I, S>(&self, args: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        match self.execute_git_fn(args, config::parse_values) {
+            Err(error) if error.exit_code() == Some(config::MISSING_EXIT_CODE) => Ok(Vec::new()),
+            result => result,
+        }
+    }
+
+    fn apply_stash(&self, command: &str, index: usize) -> Result<StashApplyOutcome> {
+        if let Err(error) = self.execute_git([
+            "stash".to_owned(),
+            command.to_owned(),
+            stash::reference(index),
+        ]) {
+            let conflicts = self.execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)?;
+            return if conflicts.is_empty() {
+                Err(error)
+            } else {
+                Ok(StashApplyOutcome::Conflicted { conflicts })
+            };
+        }
+        Ok(StashApplyOutcome::Applied)
+    }
+
+    fn run_rebase<I, S>(&self, args: I) -> Result<RebaseOutcome>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut invocation = self.invocation(args);
+        invocation.envs.extend(rebase::envs());
+        match self.run(invocation, |_| Ok(())) {
+            Ok(()) => Ok(RebaseOutcome::Completed {
+                commit: self.get_hash(false)?,
+            }),
+            Err(error) => match self.rebase_state()? {
+                Some(state) => Ok(RebaseOutcome::Stopped {
+                    state,
+                    conflicts: self
+                        .execute_git_fn(merge::CONFLICTS_ARGS, merge::parse_conflicts)?,
+                }),
+                None => Err(error),
+            },
+        }
+    }
+
+    fn execute_git<I, S>(&self, args: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.execute_git_fn(args, |_| Ok(()))
+    }
+
+    fn execute_git_fn<I, S, F, R>(&self, args: I, process: F) -> Result<R>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+        F: Fn(&str) -> Result<R>,
+    {
+        self.run(self.invocation(args), process)
+    }
+
+    fn run<F, R>(&self, invocation: Invocation, process: F) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let output = self
+            .runner
+            .run(&invocation)
+            .map_err(|source| GitError::Execution { source })?;
+        process_output(&invocation, output, process)
+    }
+
+    fn run_with_progress<F, R>(
+        &self,
+        invocation: Invocation,
+        sink: &mut dyn ProgressSink,
+        process: F,
+    ) -> Result<R>
+    where
+        F: Fn(&str) -> Result<R>,
+    {
+        let mut parser = ProgressParser::new();
+        let output = self
+            .runner
+            .run_streaming(&invocation, &mut |chunk| {
+                for progress in parser.feed(chunk) {
+                    sink.progress(progress);
+                }
+            })
+            .map_err(|source| GitError::Execution { source })?;
+        process_output(&invocation, output, process)
+    }
 }
 
-fn execute_git_fn<I, S, P, F, R>(p: P, args: I, process: F) -> Result<R>
+///Pass the stdout of a successful invocation to `process`, or turn a failed one into an error
+pub(crate) fn process_output<F, R>(
+    invocation: &Invocation,
+    output: GitOutput,
+    process: F,
+) -> Result<R>
 where
-    I: IntoIterator<Item = S>,
-    S: AsRef<OsStr>,
-    P: AsRef<Path>,
     F: Fn(&str) -> Result<R>,
 {
-    let output = Command::new("git").current_dir(p).args(args).output();
+    //Partial output may end part way through a character
+    let partial = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    match output.interrupted {
+        Some(Interruption::TimedOut) => {
+            return Err(GitError::Timeout {
+                timeout: invocation.timeout.unwrap_or_default(),
+                stdout: partial(&output.stdout),
+                stderr: partial(&output.stderr),
+                command: invocation.command_line(),
+            })
+        }
+        Some(Interruption::Cancelled) => {
+            return Err(GitError::Cancelled {
+                stdout: partial(&output.stdout),
+                stderr: partial(&output.stderr),
+                command: invocation.command_line(),
+            })
+        }
+        None => {}
+    }
 
-    output.map_err(|_| GitError::Execution).and_then(|output| {
-        if output.status.success() {
-            if let Ok(message) = str::from_utf8(&output.stdout) {
-                process(message)
-            } else {
-                Err(GitError::Undecodable)
-            }
+    if output.success() {
+        if let Ok(message) = str::from_utf8(&output.stdout) {
+            process(message)
         } else {
-            if let Ok(stdout) = str::from_utf8(&output.stdout) {
-                if let Ok(stderr) = str::from_utf8(&output.stderr) {
-                    Err(GitError::GitError {
-                        stdout: stdout.to_owned(),
-                        stderr: stderr.to_owned(),
-                    })
-                } else {
-                    Err(GitError::Undecodable)
-                }
-            } else {
-                Err(GitError::Undecodable)
-            }
+            Err(GitError::Undecodable)
+        }
+    } else if let Ok(stdout) = str::from_utf8(&output.stdout) {
+        if let Ok(stderr) = str::from_utf8(&output.stderr) {
+            Err(error::classify(
+                invocation.command_line(),
+                output.exit_code,
+                stdout.to_owned(),
+                stderr.to_owned(),
+            ))
+        } else {
+            Err(GitError::Undecodable)
         }
-    })
+    } else {
+        Err(GitError::Undecodable)
+    }
+}
+
+fn parse_lines(output: &str) -> Result<Vec<String>> {
+    Ok(output.lines().map(|line| line.to_owned()).collect())
+}
+
+fn parse_trimmed(output: &str) -> Result<String> {
+    Ok(output.trim().to_owned())
+}
+
+fn parse_optional(output: &str) -> Result<Option<String>> {
+    Ok(Some(output.trim().to_owned()).filter(|o| !o.is_empty()))
+}
+
+fn parse_remotes(output: &str) -> Result<Vec<String>> {
+    if output.trim().is_empty() {
+        Err(GitError::NoRemoteRepositorySet)
+    } else {
+        parse_lines(output)
+    }
 }
@@ -0,0 +1,320 @@
+use super::GitError;
+use crate::types::Result;
+
+const FIELDS: &[&str] = &[
+    "%H",
+    "%P",
+    "%an",
+    "%ae",
+    "%ad",
+    "%cn",
+    "%ce",
+    "%cd",
+    "%s",
+    "%b",
+    "%(trailers:only,unfold)",
+];
+
+///The identity of an author or committer along with the time they acted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    ///Seconds since the Unix epoch
+    pub timestamp: i64,
+    ///Offset from UTC in minutes
+    pub utc_offset: i32,
+}
+
+///A `Key: value` trailer at the end of a commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+///A commit as returned by `Repository::log`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    pub subject: String,
+    pub body: String,
+    pub trailers: Vec<Trailer>,
+}
+
+impl Commit {
+    ///Returns true if this commit has more than one parent
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum MergeFilter {
+    #[default]
+    All,
+    MergesOnly,
+    NoMerges,
+}
+
+///Options controlling which commits are returned by `Repository::log`
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    revisions: Vec<String>,
+    paths: Vec<String>,
+    max_count: Option<usize>,
+    skip: Option<usize>,
+    first_parent: bool,
+    author: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    merges: MergeFilter,
+}
+
+impl LogOptions {
+    pub fn new() -> LogOptions {
+        LogOptions::default()
+    }
+
+    ///Add a revision or revision range such as `main..feature`, defaults to `HEAD` if none are given
+    pub fn revision(mut self, revision: &str) -> Self {
+        self.revisions.push(revision.to_owned());
+        self
+    }
+
+    ///Only include commits touching the given path
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_owned());
+        self
+    }
+
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.max_count = Some(max_count);
+        self
+    }
+
+    pub fn skip(mut self, skip: usize) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    ///Follow only the first parent of merge commits
+    pub fn first_parent(mut self) -> Self {
+        self.first_parent = true;
+        self
+    }
+
+    ///Only include commits whose author matches the given pattern
+    pub fn author(mut self, pattern: &str) -> Self {
+        self.author = Some(pattern.to_owned());
+        self
+    }
+
+    ///Only include commits more recent than the given date, in any format understood by git
+    pub fn since(mut self, date: &str) -> Self {
+        self.since = Some(date.to_owned());
+        self
+    }
+
+    ///Only include commits older than the given date, in any format understood by git
+    pub fn until(mut self, date: &str) -> Self {
+        self.until = Some(date.to_owned());
+        self
+    }
+
+    ///Only include merge commits
+    pub fn merges_only(mut self) -> Self {
+        self.merges = MergeFilter::MergesOnly;
+        self
+    }
+
+    ///Exclude merge commits
+    pub fn no_merges(mut self) -> Self {
+        self.merges = MergeFilter::NoMerges;
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![
+            String::from("log"),
+            String::from("-z"),
+            String::from("--date=raw"),
+            format!("--format=format:{}", FIELDS.join("%x00")),
+        ];
+        if let Some(max_count) = self.max_count {
+            args.push(format!("--max-count={}", max_count));
+        }
+        if let Some(skip) = self.skip {
+            args.push(format!("--skip={}", skip));
+        }
+        if self.first_parent {
+            args.push(String::from("--first-parent"));
+        }
+        if let Some(author) = &self.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(since) = &self.since {
+            args.push(format!("--since={}", since));
+        }
+        if let Some(until) = &self.until {
+            args.push(format!("--until={}", until));
+        }
+        match self.merges {
+            MergeFilter::All => {}
+            MergeFilter::MergesOnly => args.push(String::from("--merges")),
+            MergeFilter::NoMerges => args.push(String::from("--no-merges")),
+        }
+        args.push(String::from("--end-of-options"));
+        args.extend(self.revisions.iter().cloned());
+        args.push(String::from("--"));
+        args.extend(self.paths.iter().cloned());
+        args
+    }
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+pub(crate) fn parse_signature(name: &str, email: &str, date: &str) -> Result<Signature> {
+    let mut parts = date.split(' ');
+    let timestamp = parts.next().and_then(|t| t.parse().ok());
+    let offset = parts.next().filter(|o| o.len() == 5);
+    match (timestamp, offset) {
+        (Some(timestamp), Some(offset)) => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let hours: i32 = offset[1..3].parse().map_err(|_| unexpected(date))?;
+            let minutes: i32 = offset[3..5].parse().map_err(|_| unexpected(date))?;
+            Ok(Signature {
+                name: name.to_owned(),
+                email: email.to_owned(),
+                timestamp,
+                utc_offset: sign * (hours * 60 + minutes),
+            })
+        }
+        _ => Err(unexpected(date)),
+    }
+}
+
+fn parse_trailers(trailers: &str) -> Vec<Trailer> {
+    trailers
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => Some(Trailer {
+                    key: key.trim().to_owned(),
+                    value: value.trim().to_owned(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+///Parse the output of `git log -z` using the `FIELDS` format
+pub(crate) fn parse_log(output: &str) -> Result<Vec<Commit>> {
+    if output.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let fields: Vec<&str> = output.split('\0').collect();
+    let commits = fields.chunks_exact(FIELDS.len());
+    if !commits.remainder().is_empty() {
+        return Err(unexpected(output));
+    }
+
+    commits
+        .map(|commit| {
+            Ok(Commit {
+                hash: commit[0].to_owned(),
+                parents: commit[1].split_whitespace().map(|p| p.to_owned()).collect(),
+                author: parse_signature(commit[2], commit[3], commit[4])?,
+                committer: parse_signature(commit[5], commit[6], commit[7])?,
+                subject: commit[8].to_owned(),
+                body: commit[9].trim_end().to_owned(),
+                trailers: parse_trailers(commit[10]),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let output = concat!(
+            "bbbb\0aaaa cccc\0Jane Doe\0jane@example.com\01700000000 +0130\0",
+            "John Doe\0john@example.com\01700000100 -0800\0Merge branch 'feature'\0",
+            "Some details\n\nSigned-off-by: Jane Doe <jane@example.com>\n\0",
+            "Signed-off-by: Jane Doe <jane@example.com>\n\0",
+            "aaaa\0\0Jane Doe\0jane@example.com\01600000000 +0000\0",
+            "Jane Doe\0jane@example.com\01600000000 +0000\0Initial commit\0\0",
+        );
+
+        let commits = parse_log(output).unwrap();
+
+        assert_eq!(2, commits.len());
+        assert_eq!("bbbb", commits[0].hash);
+        assert_eq!(vec!["aaaa", "cccc"], commits[0].parents);
+        assert!(commits[0].is_merge());
+        assert_eq!(
+            Signature {
+                name: String::from("Jane Doe"),
+                email: String::from("jane@example.com"),
+                timestamp: 1700000000,
+                utc_offset: 90,
+            },
+            commits[0].author
+        );
+        assert_eq!(-480, commits[0].committer.utc_offset);
+        assert_eq!("Merge branch 'feature'", commits[0].subject);
+        assert_eq!(
+            "Some details\n\nSigned-off-by: Jane Doe <jane@example.com>",
+            commits[0].body
+        );
+        assert_eq!(
+            vec![Trailer {
+                key: String::from("Signed-off-by"),
+                value: String::from("Jane Doe <jane@example.com>"),
+            }],
+            commits[0].trailers
+        );
+
+        assert!(commits[1].parents.is_empty());
+        assert!(commits[1].body.is_empty());
+        assert!(commits[1].trailers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_empty_log() {
+        assert!(parse_log("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_truncated_log() {
+        assert!(parse_log("aaaa\0\0Jane Doe").is_err());
+    }
+
+    #[test]
+    fn test_log_args() {
+        let args = LogOptions::new()
+            .revision("v1.0..HEAD")
+            .path("src")
+            .max_count(5)
+            .no_merges()
+            .args();
+
+        assert!(args.contains(&String::from("--max-count=5")));
+        assert!(args.contains(&String::from("--no-merges")));
+        assert_eq!(
+            vec!["--end-of-options", "v1.0..HEAD", "--", "src"],
+            args[args.len() - 4..].to_vec()
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use super::GitError;
+use crate::types::Result;
+
+pub(crate) const CONFLICTS_ARGS: [&str; 3] = ["ls-files", "--unmerged", "-z"];
+
+///Whether `Repository::merge` may fast-forward rather than create a merge commit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FastForward {
+    ///Fast-forward when possible, otherwise create a merge commit
+    #[default]
+    Allow,
+    ///Fail unless the merge can be fast-forwarded
+    Only,
+    ///Always create a merge commit
+    Never,
+}
+
+///Options for `Repository::merge`
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    commits: Vec<String>,
+    fast_forward: FastForward,
+    squash: bool,
+    strategy: Option<String>,
+    strategy_options: Vec<String>,
+    message: Option<String>,
+}
+
+impl MergeOptions {
+    ///Merge the given branch or commit into the current branch
+    pub fn new(commit: &str) -> MergeOptions {
+        MergeOptions {
+            commits: vec![commit.to_owned()],
+            fast_forward: FastForward::Allow,
+            squash: false,
+            strategy: None,
+            strategy_options: Vec::new(),
+            message: None,
+        }
+    }
+
+    ///Merge another branch or commit at the same time, creating an octopus merge
+    pub fn commit(mut self, commit: &str) -> Self {
+        self.commits.push(commit.to_owned());
+        self
+    }
+
+    pub fn fast_forward(mut self, fast_forward: FastForward) -> Self {
+        self.fast_forward = fast_forward;
+        self
+    }
+
+    ///Stage the merged changes without committing them or recording a merge
+    pub fn squash(mut self) -> Self {
+        self.squash = true;
+        self
+    }
+
+    ///Use the given merge strategy, such as `ort` or `ours`
+    pub fn strategy(mut self, strategy: &str) -> Self {
+        self.strategy = Some(strategy.to_owned());
+        self
+    }
+
+    ///Pass an option to the merge strategy, such as `theirs` or `ignore-space-change`
+    pub fn strategy_option(mut self, option: &str) -> Self {
+        self.strategy_options.push(option.to_owned());
+        self
+    }
+
+    ///Use the given message for the merge commit rather than git's default
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    pub(crate) fn is_squash(&self) -> bool {
+        self.squash
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("merge"), String::from("--no-edit")];
+        match self.fast_forward {
+            FastForward::Allow => {}
+            FastForward::Only => args.push(String::from("--ff-only")),
+            FastForward::Never => args.push(String::from("--no-ff")),
+        }
+        if self.squash {
+            args.push(String::from("--squash"));
+        }
+        if let Some(strategy) = &self.strategy {
+            args.push(format!("--strategy={}", strategy));
+        }
+        for option in &self.strategy_options {
+            args.push(format!("--strategy-option={}", option));
+        }
+        if let Some(message) = &self.message {
+            args.push(format!("--message={}", message));
+        }
+        args.push(String::from("--end-of-options"));
+        args.extend(self.commits.iter().cloned());
+        args
+    }
+
+    ///Arguments resolving the merged commits to hashes, to tell a fast-forward from a merge
+    pub(crate) fn target_args(&self) -> Vec<String> {
+        let mut args = vec![
+            String::from("rev-parse"),
+            String::from("--verify"),
+            String::from("--end-of-options"),
+        ];
+        args.extend(self.commits.iter().map(|c| format!("{}^{{commit}}", c)));
+        args
+    }
+}
+
+///An entry for a conflicted path at one stage of the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    ///The file mode in octal, such as `100644`
+    pub mode: String,
+    pub object: String,
+}
+
+///A path left with conflicts by a merge, with the version from each side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub path: String,
+    ///The version in the common ancestor, `None` if the path was added on both sides
+    pub base: Option<IndexEntry>,
+    ///The version on the current branch, `None` if it was deleted there
+    pub ours: Option<IndexEntry>,
+    ///The version being merged in, `None` if it was deleted there
+    pub theirs: Option<IndexEntry>,
+}
+
+///The result of `Repository::merge`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    ///Everything was already merged, nothing changed
+    UpToDate,
+    ///The current branch was moved forward to the given commit
+    FastForwarded { commit: String },
+    ///A merge commit was created
+    Merged { commit: String },
+    ///The merged changes were staged without committing, as requested by `squash`
+    Squashed,
+    ///The merge stopped with conflicts to be resolved before continuing or aborting
+    Conflicted { conflicts: Vec<Conflict> },
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the output of `git ls-files --unmerged -z`, grouping the stages of each path
+pub(crate) fn parse_conflicts(output: &str) -> Result<Vec<Conflict>> {
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for record in output.split_terminator('\0') {
+        let (info, path) = record.split_once('\t').ok_or_else(|| unexpected(record))?;
+        let fields: Vec<&str> = info.split(' ').collect();
+        if fields.len() != 3 {
+            return Err(unexpected(record));
+        }
+        let entry = Some(IndexEntry {
+            mode: fields[0].to_owned(),
+            object: fields[1].to_owned(),
+        });
+
+        if conflicts.last().map(|c| c.path.as_str()) != Some(path) {
+            conflicts.push(Conflict {
+                path: path.to_owned(),
+                base: None,
+                ours: None,
+                theirs: None,
+            });
+        }
+        let conflict = conflicts.last_mut().unwrap();
+        match fields[2] {
+            "1" => conflict.base = entry,
+            "2" => conflict.ours = entry,
+            "3" => conflict.theirs = entry,
+            _ => return Err(unexpected(record)),
+        }
+    }
+    Ok(conflicts)
+}
+
+///Work out what a successful merge did from `HEAD` before and after it
+pub(crate) fn outcome(before: Option<&str>, after: &str, targets: &[String]) -> MergeOutcome {
+    if before == Some(after) {
+        MergeOutcome::UpToDate
+    } else if targets.iter().any(|t| t == after) {
+        MergeOutcome::FastForwarded {
+            commit: after.to_owned(),
+        }
+    } else {
+        MergeOutcome::Merged {
+            commit: after.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let options = MergeOptions::new("feature")
+            .commit("other")
+            .fast_forward(FastForward::Never)
+            .strategy("ort")
+            .strategy_option("theirs")
+            .message("-Merge things");
+
+        assert_eq!(
+            vec![
+                "merge",
+                "--no-edit",
+                "--no-ff",
+                "--strategy=ort",
+                "--strategy-option=theirs",
+                "--message=-Merge things",
+                "--end-of-options",
+                "feature",
+                "other"
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec![
+                "rev-parse",
+                "--verify",
+                "--end-of-options",
+                "feature^{commit}",
+                "other^{commit}"
+            ],
+            options.target_args()
+        );
+    }
+
+    #[test]
+    fn test_parse_conflicts() {
+        let output = concat!(
+            "100644 aaaa 1\tboth modified\0",
+            "100644 bbbb 2\tboth modified\0",
+            "100644 cccc 3\tboth modified\0",
+            "100644 dddd 2\tadded by us\0",
+            "100755 eeee 3\tadded by us\0",
+        );
+
+        let conflicts = parse_conflicts(output).unwrap();
+
+        assert_eq!(
+            vec![
+                Conflict {
+                    path: String::from("both modified"),
+                    base: Some(IndexEntry {
+                        mode: String::from("100644"),
+                        object: String::from("aaaa"),
+                    }),
+                    ours: Some(IndexEntry {
+                        mode: String::from("100644"),
+                        object: String::from("bbbb"),
+                    }),
+                    theirs: Some(IndexEntry {
+                        mode: String::from("100644"),
+                        object: String::from("cccc"),
+                    }),
+                },
+                Conflict {
+                    path: String::from("added by us"),
+                    base: None,
+                    ours: Some(IndexEntry {
+                        mode: String::from("100644"),
+                        object: String::from("dddd"),
+                    }),
+                    theirs: Some(IndexEntry {
+                        mode: String::from("100755"),
+                        object: String::from("eeee"),
+                    }),
+                },
+            ],
+            conflicts
+        );
+        assert!(parse_conflicts("").unwrap().is_empty());
+        assert!(parse_conflicts("100644 aaaa 4\tpath\0").is_err());
+    }
+
+    #[test]
+    fn test_outcome() {
+        let targets = vec![String::from("bbbb")];
+
+        assert_eq!(
+            MergeOutcome::UpToDate,
+            outcome(Some("aaaa"), "aaaa", &targets)
+        );
+        assert_eq!(
+            MergeOutcome::FastForwarded {
+                commit: String::from("bbbb")
+            },
+            outcome(Some("aaaa"), "bbbb", &targets)
+        );
+        assert_eq!(
+            MergeOutcome::Merged {
+                commit: String::from("cccc")
+            },
+            outcome(Some("aaaa"), "cccc", &targets)
+        );
+    }
+}
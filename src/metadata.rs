@@ -0,0 +1,100 @@
+use super::GitError;
+use crate::types::Result;
+use std::path::PathBuf;
+
+pub(crate) const DISCOVER_ARGS: [&str; 6] = [
+    "rev-parse",
+    "--path-format=absolute",
+    "--is-bare-repository",
+    "--is-inside-work-tree",
+    "--git-dir",
+    "--git-common-dir",
+];
+pub(crate) const TOPLEVEL_ARGS: [&str; 3] =
+    ["rev-parse", "--path-format=absolute", "--show-toplevel"];
+pub(crate) const SHALLOW_ARGS: [&str; 2] = ["rev-parse", "--is-shallow-repository"];
+pub(crate) const OBJECT_FORMAT_ARGS: [&str; 2] = ["rev-parse", "--show-object-format"];
+
+///The hash algorithm a repository uses to name objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFormat {
+    Sha1,
+    Sha256,
+}
+
+///Where the parts of a repository were found by `git rev-parse`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Discovery {
+    pub(crate) bare: bool,
+    ///Whether the search started inside the working tree, rather than in the git directory
+    pub(crate) inside_work_tree: bool,
+    pub(crate) git_dir: PathBuf,
+    ///The git directory shared by all worktrees, which differs from `git_dir` in a linked
+    ///worktree
+    pub(crate) common_dir: PathBuf,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+pub(crate) fn parse_bool(output: &str) -> Result<bool> {
+    match output.trim() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(unexpected(output)),
+    }
+}
+
+///Parse the output of `git rev-parse` with `DISCOVER_ARGS`, one answer per line
+pub(crate) fn parse_discovery(output: &str) -> Result<Discovery> {
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() != 4 {
+        return Err(unexpected(output));
+    }
+    Ok(Discovery {
+        bare: parse_bool(lines[0])?,
+        inside_work_tree: parse_bool(lines[1])?,
+        git_dir: PathBuf::from(lines[2]),
+        common_dir: PathBuf::from(lines[3]),
+    })
+}
+
+pub(crate) fn parse_object_format(output: &str) -> Result<ObjectFormat> {
+    match output.trim() {
+        "sha1" => Ok(ObjectFormat::Sha1),
+        "sha256" => Ok(ObjectFormat::Sha256),
+        _ => Err(unexpected(output)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_discovery() {
+        let output = "false\ntrue\n/srv/ci/.git/worktrees/ci\n/srv/repo/.git\n";
+
+        assert_eq!(
+            Discovery {
+                bare: false,
+                inside_work_tree: true,
+                git_dir: PathBuf::from("/srv/ci/.git/worktrees/ci"),
+                common_dir: PathBuf::from("/srv/repo/.git"),
+            },
+            parse_discovery(output).unwrap()
+        );
+        assert!(parse_discovery("true\n/srv/repo.git\n").is_err());
+        assert!(parse_discovery("yes\nno\n/a\n/b\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_object_format() {
+        assert_eq!(
+            ObjectFormat::Sha256,
+            parse_object_format("sha256\n").unwrap()
+        );
+        assert!(parse_object_format("md5\n").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use regex::Regex;
+use std::sync::mpsc::{Sender, SyncSender};
+
+///The phase of a transfer that a `Progress` event refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stage {
+    Enumerating,
+    Counting,
+    Compressing,
+    Receiving,
+    Resolving,
+    Unpacking,
+    Writing,
+    ///Checking out files into the working tree after a clone
+    CheckingOut,
+    ///Any other stage git reports, named as git prints it
+    Other(String),
+}
+
+impl Stage {
+    fn from_label(label: &str) -> Stage {
+        match label {
+            "Enumerating objects" => Stage::Enumerating,
+            "Counting objects" => Stage::Counting,
+            "Compressing objects" => Stage::Compressing,
+            "Receiving objects" => Stage::Receiving,
+            "Resolving deltas" => Stage::Resolving,
+            "Unpacking objects" => Stage::Unpacking,
+            "Writing objects" => Stage::Writing,
+            "Updating files" => Stage::CheckingOut,
+            _ => Stage::Other(label.to_owned()),
+        }
+    }
+}
+
+///A progress update reported by git during a clone, fetch or push
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub stage: Stage,
+    ///Whether the update was reported by the remote rather than the local git
+    pub remote: bool,
+    pub current: u64,
+    ///The total number of items, `None` if git does not know it in advance
+    pub total: Option<u64>,
+    pub percent: Option<u8>,
+    ///The number of bytes transferred so far
+    pub bytes: Option<u64>,
+    ///The transfer rate in bytes per second
+    pub throughput: Option<u64>,
+    ///Set on the final update of a stage
+    pub done: bool,
+}
+
+///Receives progress updates while a command runs
+///
+///Implemented for closures taking a `Progress` and for the sending half of a channel.
+pub trait ProgressSink {
+    fn progress(&mut self, progress: Progress);
+}
+
+impl<F: FnMut(Progress)> ProgressSink for F {
+    fn progress(&mut self, progress: Progress) {
+        self(progress)
+    }
+}
+
+impl ProgressSink for Sender<Progress> {
+    fn progress(&mut self, progress: Progress) {
+        //The receiver going away shouldn't abort the command
+        let _ = self.send(progress);
+    }
+}
+
+impl ProgressSink for SyncSender<Progress> {
+    fn progress(&mut self, progress: Progress) {
+        let _ = self.send(progress);
+    }
+}
+
+///Turns chunks of git's stderr into progress updates
+///
+///Git separates updates with carriage returns and lines with newlines. Chunks may end part
+///way through an update, so any trailing partial update is held back until the next chunk.
+pub(crate) struct ProgressParser {
+    pattern: Regex,
+    pending: Vec<u8>,
+}
+
+impl ProgressParser {
+    pub(crate) fn new() -> ProgressParser {
+        ProgressParser {
+            pattern: Regex::new(concat!(
+                r"^(?P<stage>[A-Za-z][A-Za-z ]*):\s+",
+                r"(?:(?P<percent>\d+)% \((?P<current>\d+)/(?P<total>\d+)\)|(?P<count>\d+))",
+                r"(?:, (?P<bytes>[\d.]+ (?:bytes|[KMGT]iB))",
+                r"(?: \| (?P<rate>[\d.]+ (?:bytes|[KMGT]iB))/s)?)?",
+                r"(?P<done>, done)?"
+            ))
+            .unwrap(),
+            pending: Vec::new(),
+        }
+    }
+
+    ///Parse the updates completed by `chunk`
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Vec<Progress> {
+        self.pending.extend_from_slice(chunk);
+        let end = match self.pending.iter().rposition(|&b| b == b'\r' || b == b'\n') {
+            Some(end) => end,
+            None => return Vec::new(),
+        };
+        let complete: Vec<u8> = self.pending.drain(..=end).collect();
+        String::from_utf8_lossy(&complete)
+            .split(['\r', '\n'])
+            .filter_map(|update| self.parse(update))
+            .collect()
+    }
+
+    fn parse(&self, update: &str) -> Option<Progress> {
+        let (remote, update) = match update.strip_prefix("remote: ") {
+            Some(update) => (true, update),
+            None => (false, update),
+        };
+        let captures = self.pattern.captures(update.trim())?;
+        let number = |name| captures.name(name).and_then(|m| m.as_str().parse().ok());
+
+        Some(Progress {
+            stage: Stage::from_label(&captures["stage"]),
+            remote,
+            current: number("current").or_else(|| number("count"))?,
+            total: number("total"),
+            percent: number("percent").map(|p: u64| p.min(100) as u8),
+            bytes: captures.name("bytes").and_then(|m| parse_size(m.as_str())),
+            throughput: captures.name("rate").and_then(|m| parse_size(m.as_str())),
+            done: captures.name("done").is_some(),
+        })
+    }
+}
+
+///Parse a size as printed by git, such as `256 bytes` or `1.50 MiB`
+fn parse_size(size: &str) -> Option<u64> {
+    let mut parts = size.split(' ');
+    let value: f64 = parts.next()?.parse().ok()?;
+    let multiplier = match parts.next()? {
+        "bytes" => 1u64,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * multiplier as f64).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_progress() {
+        let mut parser = ProgressParser::new();
+
+        let updates = parser.feed(
+            concat!(
+                "Cloning into 'dst'...\n",
+                "remote: Enumerating objects: 9, done.        \n",
+                "remote: Counting objects:  11% (1/9)        \r",
+                "remote: Counting objects: 100% (9/9), done.        \n",
+                "remote: Total 9 (delta 1), reused 0 (delta 0), pack-reused 0        \n",
+            )
+            .as_bytes(),
+        );
+
+        assert_eq!(
+            vec![
+                Progress {
+                    stage: Stage::Enumerating,
+                    remote: true,
+                    current: 9,
+                    total: None,
+                    percent: None,
+                    bytes: None,
+                    throughput: None,
+                    done: true,
+                },
+                Progress {
+                    stage: Stage::Counting,
+                    remote: true,
+                    current: 1,
+                    total: Some(9),
+                    percent: Some(11),
+                    bytes: None,
+                    throughput: None,
+                    done: false,
+                },
+                Progress {
+                    stage: Stage::Counting,
+                    remote: true,
+                    current: 9,
+                    total: Some(9),
+                    percent: Some(100),
+                    bytes: None,
+                    throughput: None,
+                    done: true,
+                },
+            ],
+            updates
+        );
+    }
+
+    #[test]
+    fn test_parse_transfer_progress_across_chunks() {
+        let mut parser = ProgressParser::new();
+
+        assert!(parser
+            .feed(b"Receiving objects:  45% (123/273), 1.")
+            .is_empty());
+        let updates = parser.feed(b"50 MiB | 512.00 KiB/s\rWriting objects: 100% (3/3), 256 bytes | 256.00 KiB/s, done.\nResolving");
+
+        assert_eq!(2, updates.len());
+        assert_eq!(Stage::Receiving, updates[0].stage);
+        assert!(!updates[0].remote);
+        assert_eq!(
+            (123, Some(273), Some(45)),
+            (updates[0].current, updates[0].total, updates[0].percent)
+        );
+        assert_eq!(Some(1572864), updates[0].bytes);
+        assert_eq!(Some(524288), updates[0].throughput);
+        assert!(!updates[0].done);
+        assert_eq!(Stage::Writing, updates[1].stage);
+        assert_eq!(Some(256), updates[1].bytes);
+        assert!(updates[1].done);
+
+        let updates = parser.feed(b" deltas: 100% (1/1), done.\n");
+        assert_eq!(Stage::Resolving, updates[0].stage);
+    }
+
+    #[test]
+    fn test_parse_other_stage() {
+        let mut parser = ProgressParser::new();
+
+        let updates = parser.feed(b"Filtering content:  50% (1/2)\rerror: something else\n");
+
+        assert_eq!(1, updates.len());
+        assert_eq!(
+            Stage::Other(String::from("Filtering content")),
+            updates[0].stage
+        );
+    }
+}
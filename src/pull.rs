@@ -0,0 +1,233 @@
+use crate::fetch::Tags;
+use crate::merge::Conflict;
+
+///Resolves the commit `git pull` fetched to integrate, which it lists first in `FETCH_HEAD`
+pub(crate) const FETCHED_ARGS: [&str; 3] = ["rev-parse", "--verify", "FETCH_HEAD^{commit}"];
+
+///How `Repository::pull` integrates the fetched commits into the current branch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PullMode {
+    ///Fast-forward when possible, otherwise create a merge commit
+    #[default]
+    Merge,
+    ///Replay local commits on top of the fetched ones
+    Rebase,
+    ///Fail unless the branch can be fast-forwarded
+    FastForwardOnly,
+}
+
+///Options for `Repository::pull`
+///
+///By default the current branch's upstream is pulled and merged.
+#[derive(Debug, Clone)]
+pub struct PullOptions {
+    remote: Option<String>,
+    branch: Option<String>,
+    mode: PullMode,
+    autostash: bool,
+    depth: Option<u32>,
+    tags: Tags,
+}
+
+impl Default for PullOptions {
+    fn default() -> PullOptions {
+        PullOptions {
+            remote: None,
+            branch: None,
+            mode: PullMode::Merge,
+            autostash: false,
+            depth: None,
+            tags: Tags::Default,
+        }
+    }
+}
+
+impl PullOptions {
+    pub fn new() -> PullOptions {
+        PullOptions::default()
+    }
+
+    ///Pull from the given remote rather than the upstream's
+    pub fn remote(mut self, remote: &str) -> Self {
+        self.remote = Some(remote.to_owned());
+        self
+    }
+
+    ///Pull the given branch from the remote, `origin` unless another remote is set
+    pub fn branch(mut self, branch: &str) -> Self {
+        self.branch = Some(branch.to_owned());
+        self
+    }
+
+    pub fn mode(mut self, mode: PullMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    ///Stash local changes before pulling and reapply them afterwards
+    pub fn autostash(mut self) -> Self {
+        self.autostash = true;
+        self
+    }
+
+    ///Fetch history truncated to the given number of commits
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    ///Fetch all tags from the remote as well
+    pub fn tags(mut self) -> Self {
+        self.tags = Tags::All;
+        self
+    }
+
+    ///Don't fetch any tags, even those pointing at fetched commits
+    pub fn no_tags(mut self) -> Self {
+        self.tags = Tags::None;
+        self
+    }
+
+    pub(crate) fn is_rebase(&self) -> bool {
+        self.mode == PullMode::Rebase
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("pull"), String::from("--no-edit")];
+        args.push(String::from(match self.mode {
+            PullMode::Merge => "--no-rebase",
+            PullMode::Rebase => "--rebase",
+            PullMode::FastForwardOnly => "--ff-only",
+        }));
+        if self.autostash {
+            args.push(String::from("--autostash"));
+        }
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={}", depth));
+        }
+        args.extend(self.tags.arg().map(String::from));
+        match (&self.remote, &self.branch) {
+            (None, None) => {}
+            (remote, branch) => {
+                args.push(String::from("--end-of-options"));
+                args.push(remote.clone().unwrap_or_else(|| String::from("origin")));
+                args.extend(branch.iter().cloned());
+            }
+        }
+        args
+    }
+}
+
+///The result of `Repository::pull`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullOutcome {
+    ///The branch already contained everything fetched
+    UpToDate,
+    ///The branch was moved forward, from `None` if it had no commits yet
+    FastForwarded { from: Option<String>, to: String },
+    ///A merge commit was created
+    Merged { commit: String },
+    ///Local commits were replayed on top of the fetched ones
+    Rebased { commit: String },
+    ///The merge or rebase stopped with conflicts to be resolved before continuing or aborting
+    Conflicted { conflicts: Vec<Conflict> },
+}
+
+///Work out what a successful pull did from `HEAD` before and after it, and the fetched commit
+pub(crate) fn outcome(
+    before: Option<String>,
+    after: String,
+    fetched: &str,
+    rebase: bool,
+) -> PullOutcome {
+    if before.as_deref() == Some(after.as_str()) {
+        PullOutcome::UpToDate
+    } else if after == fetched {
+        PullOutcome::FastForwarded {
+            from: before,
+            to: after,
+        }
+    } else if rebase {
+        PullOutcome::Rebased { commit: after }
+    } else {
+        PullOutcome::Merged { commit: after }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let options = PullOptions::new()
+            .branch("main")
+            .mode(PullMode::Rebase)
+            .autostash()
+            .depth(10)
+            .no_tags();
+
+        assert_eq!(
+            vec![
+                "pull",
+                "--no-edit",
+                "--rebase",
+                "--autostash",
+                "--depth=10",
+                "--no-tags",
+                "--end-of-options",
+                "origin",
+                "main"
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec![
+                "pull",
+                "--no-edit",
+                "--ff-only",
+                "--tags",
+                "--end-of-options",
+                "upstream"
+            ],
+            PullOptions::new()
+                .remote("upstream")
+                .mode(PullMode::FastForwardOnly)
+                .tags()
+                .args()
+        );
+        assert_eq!(
+            vec!["pull", "--no-edit", "--no-rebase"],
+            PullOptions::new().args()
+        );
+    }
+
+    #[test]
+    fn test_outcome() {
+        let head = || Some(String::from("aaaa"));
+
+        assert_eq!(
+            PullOutcome::UpToDate,
+            outcome(head(), String::from("aaaa"), "bbbb", false)
+        );
+        assert_eq!(
+            PullOutcome::FastForwarded {
+                from: head(),
+                to: String::from("bbbb")
+            },
+            outcome(head(), String::from("bbbb"), "bbbb", true)
+        );
+        assert_eq!(
+            PullOutcome::Rebased {
+                commit: String::from("cccc")
+            },
+            outcome(head(), String::from("cccc"), "bbbb", true)
+        );
+        assert_eq!(
+            PullOutcome::Merged {
+                commit: String::from("cccc")
+            },
+            outcome(None, String::from("cccc"), "bbbb", false)
+        );
+    }
+}
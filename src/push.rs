@@ -0,0 +1,333 @@
+use super::GitError;
+use crate::types::Result;
+
+///Options for `Repository::push_with`
+///
+///By default the current branch is pushed as configured by `push.default`.
+#[derive(Debug, Clone, Default)]
+pub struct PushOptions {
+    remote: Option<String>,
+    refspecs: Vec<String>,
+    force: bool,
+    leases: Vec<String>,
+    atomic: bool,
+    delete: bool,
+    tags: bool,
+    follow_tags: bool,
+    push_options: Vec<String>,
+    dry_run: bool,
+}
+
+impl PushOptions {
+    pub fn new() -> PushOptions {
+        PushOptions::default()
+    }
+
+    pub fn remote(mut self, remote: &str) -> Self {
+        self.remote = Some(remote.to_owned());
+        self
+    }
+
+    ///Push the given refspec, such as `main:refs/heads/release`, to `origin` unless another
+    ///remote is set
+    pub fn refspec(mut self, refspec: &str) -> Self {
+        self.refspecs.push(refspec.to_owned());
+        self
+    }
+
+    ///Update remote refs even if the update isn't a fast-forward
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    ///Update the given remote ref even if the update isn't a fast-forward, but only if it
+    ///still points at `expected`, or at the remote-tracking branch for it if not given
+    pub fn force_with_lease(mut self, reference: &str, expected: Option<&str>) -> Self {
+        self.leases.push(match expected {
+            Some(expected) => format!("{}:{}", reference, expected),
+            None => reference.to_owned(),
+        });
+        self
+    }
+
+    ///Update either all refs or none of them
+    pub fn atomic(mut self) -> Self {
+        self.atomic = true;
+        self
+    }
+
+    ///Delete the refs named by the refspecs from the remote
+    pub fn delete(mut self) -> Self {
+        self.delete = true;
+        self
+    }
+
+    ///Push all local tags as well
+    pub fn tags(mut self) -> Self {
+        self.tags = true;
+        self
+    }
+
+    ///Push annotated tags pointing at pushed commits as well
+    pub fn follow_tags(mut self) -> Self {
+        self.follow_tags = true;
+        self
+    }
+
+    ///Pass the given string to the remote's hooks, such as `ci.skip`
+    pub fn push_option(mut self, option: &str) -> Self {
+        self.push_options.push(option.to_owned());
+        self
+    }
+
+    ///Report what would be pushed without updating the remote
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("push"), String::from("--porcelain")];
+        if self.force {
+            args.push(String::from("--force"));
+        }
+        args.extend(
+            self.leases
+                .iter()
+                .map(|lease| format!("--force-with-lease={}", lease)),
+        );
+        if self.atomic {
+            args.push(String::from("--atomic"));
+        }
+        if self.delete {
+            args.push(String::from("--delete"));
+        }
+        if self.tags {
+            args.push(String::from("--tags"));
+        }
+        if self.follow_tags {
+            args.push(String::from("--follow-tags"));
+        }
+        args.extend(
+            self.push_options
+                .iter()
+                .map(|option| format!("--push-option={}", option)),
+        );
+        if self.dry_run {
+            args.push(String::from("--dry-run"));
+        }
+        if self.remote.is_some() || !self.refspecs.is_empty() {
+            args.push(String::from("--end-of-options"));
+            args.push(
+                self.remote
+                    .clone()
+                    .unwrap_or_else(|| String::from("origin")),
+            );
+            args.extend(self.refspecs.iter().cloned());
+        }
+        args
+    }
+}
+
+///What happened to a ref pushed by `Repository::push_with`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushStatus {
+    FastForward,
+    ///The remote ref was updated to a commit which doesn't contain its old value
+    Forced,
+    New,
+    Deleted,
+    UpToDate,
+    ///The push was refused before being sent, such as a non fast-forward or a stale lease
+    Rejected {
+        reason: String,
+    },
+    ///The remote refused the update, such as when a hook declines it
+    RemoteRejected {
+        message: String,
+    },
+}
+
+impl PushStatus {
+    ///Whether the remote ref now has the value pushed
+    pub fn is_ok(&self) -> bool {
+        !matches!(
+            self,
+            PushStatus::Rejected { .. } | PushStatus::RemoteRejected { .. }
+        )
+    }
+}
+
+///The outcome of pushing a single ref with `Repository::push_with`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushResult {
+    ///The full name of the local ref pushed, `None` when deleting
+    pub source: Option<String>,
+    ///The full name of the remote ref, such as `refs/heads/main`
+    pub reference: String,
+    pub status: PushStatus,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///The text in brackets at the end of a summary such as `[rejected] (non-fast-forward)`
+fn reason(summary: &str, prefix: &str) -> Option<String> {
+    summary
+        .strip_prefix(prefix)
+        .map(|rest| rest.trim().trim_start_matches('(').trim_end_matches(')'))
+        .map(|reason| reason.to_owned())
+}
+
+///Parse the output of `git push --porcelain`, where each pushed ref is a line with a flag, the
+///local and remote refs separated by a colon and a summary, separated by tabs
+///
+///These are preceded by a `To` line for each URL pushed to and followed by `Done`.
+pub(crate) fn parse_results(output: &str) -> Result<Vec<PushResult>> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with("To ") && *line != "Done")
+        .map(|line| {
+            let fields: Vec<&str> = line.splitn(3, '\t').collect();
+            if fields.len() != 3 {
+                return Err(unexpected(line));
+            }
+            let (source, reference) = fields[1].split_once(':').ok_or_else(|| unexpected(line))?;
+            let summary = fields[2];
+            let status = match fields[0] {
+                " " => PushStatus::FastForward,
+                "+" => PushStatus::Forced,
+                "*" => PushStatus::New,
+                "-" => PushStatus::Deleted,
+                "=" => PushStatus::UpToDate,
+                "!" => match reason(summary, "[remote rejected]") {
+                    Some(message) => PushStatus::RemoteRejected { message },
+                    None => {
+                        let reason = reason(summary, "[rejected]");
+                        PushStatus::Rejected {
+                            reason: reason.unwrap_or_else(|| summary.to_owned()),
+                        }
+                    }
+                },
+                _ => return Err(unexpected(line)),
+            };
+            Ok(PushResult {
+                source: Some(source.to_owned()).filter(|s| !s.is_empty()),
+                reference: reference.to_owned(),
+                status,
+            })
+        })
+        .collect()
+}
+
+///The results reported by a push which failed because some refs were rejected, or the error
+///if it failed for any other reason
+pub(crate) fn rejected_results(error: GitError) -> Result<Vec<PushResult>> {
+    if let GitError::GitError { stdout, .. } = &error {
+        if let Ok(results) = parse_results(stdout) {
+            if results.iter().any(|r| !r.status.is_ok()) {
+                return Ok(results);
+            }
+        }
+    }
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let options = PushOptions::new()
+            .refspec("main")
+            .refspec("feature:refs/heads/review")
+            .force_with_lease("main", Some("aaaa"))
+            .force_with_lease("review", None)
+            .atomic()
+            .follow_tags()
+            .push_option("ci.skip")
+            .dry_run();
+
+        assert_eq!(
+            vec![
+                "push",
+                "--porcelain",
+                "--force-with-lease=main:aaaa",
+                "--force-with-lease=review",
+                "--atomic",
+                "--follow-tags",
+                "--push-option=ci.skip",
+                "--dry-run",
+                "--end-of-options",
+                "origin",
+                "main",
+                "feature:refs/heads/review"
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec![
+                "push",
+                "--porcelain",
+                "--delete",
+                "--end-of-options",
+                "upstream",
+                "old"
+            ],
+            PushOptions::new()
+                .remote("upstream")
+                .delete()
+                .refspec("old")
+                .args()
+        );
+        assert_eq!(vec!["push", "--porcelain"], PushOptions::new().args());
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let output = concat!(
+            "To /srv/repo.git\n",
+            " \trefs/heads/main:refs/heads/main\taaaa..bbbb\n",
+            "+\trefs/heads/ci:refs/heads/ci\taaaa...cccc (forced update)\n",
+            "-\t:refs/heads/old\t[deleted]\n",
+            "=\trefs/tags/v1:refs/tags/v1\t[up to date]\n",
+            "!\trefs/heads/dev:refs/heads/dev\t[rejected] (stale info)\n",
+            "!\trefs/heads/x:refs/heads/x\t[remote rejected] (pre-receive hook declined)\n",
+            "Done\n",
+        );
+
+        let results = parse_results(output).unwrap();
+
+        assert_eq!(6, results.len());
+        assert_eq!(
+            PushResult {
+                source: Some(String::from("refs/heads/main")),
+                reference: String::from("refs/heads/main"),
+                status: PushStatus::FastForward,
+            },
+            results[0]
+        );
+        assert_eq!(PushStatus::Forced, results[1].status);
+        assert_eq!(None, results[2].source);
+        assert_eq!(PushStatus::Deleted, results[2].status);
+        assert_eq!(PushStatus::UpToDate, results[3].status);
+        assert_eq!(
+            PushStatus::Rejected {
+                reason: String::from("stale info")
+            },
+            results[4].status
+        );
+        assert_eq!(
+            PushStatus::RemoteRejected {
+                message: String::from("pre-receive hook declined")
+            },
+            results[5].status
+        );
+        assert!(!results[5].status.is_ok());
+        assert!(parse_results("?\trefs/heads/main:refs/heads/main\t[new]\n").is_err());
+        assert!(parse_results("*\trefs/heads/main\n").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use super::GitError;
+use crate::merge::Conflict;
+use crate::types::Result;
+use std::ffi::OsString;
+
+///The directories git keeps the state of an in-progress rebase in, for the merge and apply
+///backends respectively
+pub(crate) const STATE_DIRS: [&str; 2] = ["rebase-merge", "rebase-apply"];
+
+///The files within a state directory used by `parse_state`
+pub(crate) const STATE_FILES: [&str; 9] = [
+    "msgnum",
+    "end",
+    "next",
+    "last",
+    "stopped-sha",
+    "original-commit",
+    "done",
+    "head-name",
+    "onto",
+];
+
+///`git am` also uses `rebase-apply`, a rebase is marked by this file
+pub(crate) const APPLY_REBASING_MARKER: &str = "rebasing";
+
+///Options for `Repository::rebase`
+#[derive(Debug, Clone, Default)]
+pub struct RebaseOptions {
+    upstream: Option<String>,
+    onto: Option<String>,
+    autosquash: bool,
+    rebase_merges: bool,
+    autostash: bool,
+    exec: Vec<String>,
+}
+
+impl RebaseOptions {
+    ///Rebase the current branch onto its configured upstream
+    pub fn new() -> RebaseOptions {
+        RebaseOptions::default()
+    }
+
+    ///Replay the commits not in the given upstream, rather than the configured one
+    pub fn upstream(mut self, upstream: &str) -> Self {
+        self.upstream = Some(upstream.to_owned());
+        self
+    }
+
+    ///Replay the commits onto the given commit rather than onto the upstream
+    pub fn onto(mut self, onto: &str) -> Self {
+        self.onto = Some(onto.to_owned());
+        self
+    }
+
+    ///Squash `fixup!` and `squash!` commits into the commits they refer to
+    pub fn autosquash(mut self) -> Self {
+        self.autosquash = true;
+        self
+    }
+
+    ///Recreate merge commits rather than flattening history
+    pub fn rebase_merges(mut self) -> Self {
+        self.rebase_merges = true;
+        self
+    }
+
+    ///Stash local changes before rebasing and reapply them afterwards
+    pub fn autostash(mut self) -> Self {
+        self.autostash = true;
+        self
+    }
+
+    ///Run the given shell command after each commit is replayed, stopping if it fails
+    pub fn exec(mut self, command: &str) -> Self {
+        self.exec.push(command.to_owned());
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("rebase")];
+        if self.autosquash {
+            //Older versions of git only autosquash in interactive mode, the todo list is
+            //accepted as is by the sequence editor set in `envs`
+            args.push(String::from("--interactive"));
+            args.push(String::from("--autosquash"));
+        }
+        if self.rebase_merges {
+            args.push(String::from("--rebase-merges"));
+        }
+        if self.autostash {
+            args.push(String::from("--autostash"));
+        }
+        for command in &self.exec {
+            args.push(format!("--exec={}", command));
+        }
+        if let Some(onto) = &self.onto {
+            args.push(format!("--onto={}", onto));
+        }
+        args.push(String::from("--end-of-options"));
+        args.extend(self.upstream.iter().cloned());
+        args
+    }
+}
+
+///Environment for every rebase invocation, so git never waits on an editor
+pub(crate) fn envs() -> Vec<(OsString, OsString)> {
+    vec![
+        ("GIT_SEQUENCE_EDITOR".into(), ":".into()),
+        ("GIT_EDITOR".into(), ":".into()),
+    ]
+}
+
+///The progress of a rebase that has stopped part way through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebaseState {
+    ///The step the rebase stopped at, starting from 1
+    pub current: usize,
+    pub total: usize,
+    ///The commit being applied when the rebase stopped, if any
+    pub commit: Option<String>,
+    ///The branch being rebased, `None` if rebasing a detached `HEAD`
+    pub branch: Option<String>,
+    ///The commit the branch is being rebased onto
+    pub onto: String,
+}
+
+///The result of `Repository::rebase` and of continuing or skipping a stopped rebase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebaseOutcome {
+    ///All commits were replayed, leaving `HEAD` at the given commit
+    Completed { commit: String },
+    ///The rebase stopped due to conflicts or a failing `exec` command, to be continued,
+    ///skipped or aborted
+    Stopped {
+        state: RebaseState,
+        conflicts: Vec<Conflict>,
+    },
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///The commit applied by a line of the `done` list, `None` for commands such as `exec`,
+///`label` and `reset` which don't apply one
+fn done_commit(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    let commit = match words.next()? {
+        "pick" | "p" | "edit" | "e" | "reword" | "r" | "squash" | "s" | "drop" | "d" => {
+            words.next()
+        }
+        //`fixup -C` and `merge -C` take the message from the given commit
+        "fixup" | "f" | "merge" | "m" => match words.next()? {
+            "-C" | "-c" => words.next(),
+            word => Some(word),
+        },
+        _ => None,
+    }?;
+    Some(commit).filter(|c| c.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+///Build the state of a rebase from the files in its state directory, read with `read`
+///
+///The merge backend records progress in `msgnum` and `end`, the apply backend in `next`
+///and `last`.
+pub(crate) fn parse_state<F>(read: F) -> Result<RebaseState>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let number = |names: [&str; 2]| -> Result<usize> {
+        let value = names
+            .iter()
+            .find_map(|name| read(name))
+            .ok_or_else(|| unexpected(names[0]))?;
+        value.trim().parse().map_err(|_| unexpected(&value))
+    };
+    let done = read("done").and_then(|done| {
+        done.lines()
+            .rev()
+            .find_map(done_commit)
+            .map(|c| c.to_owned())
+    });
+    let commit = read("stopped-sha")
+        .or_else(|| read("original-commit"))
+        .map(|c| c.trim().to_owned())
+        .or(done);
+    let branch = read("head-name").and_then(|head| {
+        head.trim()
+            .strip_prefix("refs/heads/")
+            .map(|branch| branch.to_owned())
+    });
+
+    Ok(RebaseState {
+        current: number(["msgnum", "next"])?,
+        total: number(["end", "last"])?,
+        commit,
+        branch,
+        onto: read("onto")
+            .map(|o| o.trim().to_owned())
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_args() {
+        let options = RebaseOptions::new()
+            .upstream("origin/main")
+            .onto("release")
+            .autosquash()
+            .rebase_merges()
+            .autostash()
+            .exec("cargo test");
+
+        assert_eq!(
+            vec![
+                "rebase",
+                "--interactive",
+                "--autosquash",
+                "--rebase-merges",
+                "--autostash",
+                "--exec=cargo test",
+                "--onto=release",
+                "--end-of-options",
+                "origin/main",
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec!["rebase", "--end-of-options"],
+            RebaseOptions::new().args()
+        );
+    }
+
+    #[test]
+    fn test_parse_merge_state() {
+        let files = HashMap::from([
+            ("msgnum", "2\n"),
+            ("end", "5\n"),
+            ("head-name", "refs/heads/feature\n"),
+            ("onto", "aaaa\n"),
+            ("done", "pick bbbb first\npick cccc second\n"),
+        ]);
+
+        let state = parse_state(|name| files.get(name).map(|f| f.to_string())).unwrap();
+
+        assert_eq!(
+            RebaseState {
+                current: 2,
+                total: 5,
+                commit: Some(String::from("cccc")),
+                branch: Some(String::from("feature")),
+                onto: String::from("aaaa"),
+            },
+            state
+        );
+    }
+
+    #[test]
+    fn test_parse_exec_state() {
+        let files = HashMap::from([
+            ("msgnum", "2\n"),
+            ("end", "2\n"),
+            ("done", "pick bbbb first\nexec false\n"),
+        ]);
+
+        let state = parse_state(|name| files.get(name).map(|f| f.to_string())).unwrap();
+
+        assert_eq!(Some(String::from("bbbb")), state.commit);
+
+        let files = HashMap::from([
+            ("msgnum", "3\n"),
+            ("end", "4\n"),
+            (
+                "done",
+                "label onto\nreset onto\npick cccc side\nmerge -C dddd side # Merge\nbreak\n",
+            ),
+        ]);
+
+        let state = parse_state(|name| files.get(name).map(|f| f.to_string())).unwrap();
+
+        assert_eq!(Some(String::from("dddd")), state.commit);
+
+        let files = HashMap::from([
+            ("msgnum", "1\n"),
+            ("end", "2\n"),
+            ("done", "label onto\nupdate-ref refs/heads/other\n"),
+        ]);
+
+        let state = parse_state(|name| files.get(name).map(|f| f.to_string())).unwrap();
+
+        assert_eq!(None, state.commit);
+    }
+
+    #[test]
+    fn test_parse_apply_state() {
+        let files = HashMap::from([
+            ("next", "1\n"),
+            ("last", "3\n"),
+            ("head-name", "detached HEAD\n"),
+            ("original-commit", "dddd\n"),
+        ]);
+
+        let state = parse_state(|name| files.get(name).map(|f| f.to_string())).unwrap();
+
+        assert_eq!((1, 3), (state.current, state.total));
+        assert_eq!(Some(String::from("dddd")), state.commit);
+        assert_eq!(None, state.branch);
+        assert!(parse_state(|_| None).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use super::GitError;
+use crate::types::Result;
+
+pub(crate) const VERBOSE_LIST_ARGS: [&str; 2] = ["remote", "-v"];
+pub(crate) const REFSPECS_ARGS: [&str; 4] = [
+    "config",
+    "-z",
+    "--get-regexp",
+    r"^remote\..*\.(fetch|push)$",
+];
+
+///What `Repository::remote_set_head` points a remote's `HEAD` at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteHead {
+    ///Ask the remote which branch its `HEAD` points at
+    Auto,
+    ///The given branch of the remote, which must have been fetched
+    Branch(String),
+    ///Remove the remote's `HEAD`
+    Delete,
+}
+
+///A remote as returned by `Repository::remotes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    pub name: String,
+    ///The URL fetched from, after any `url.<base>.insteadOf` rewriting
+    pub fetch_url: Option<String>,
+    ///The URLs pushed to, which are the configured push URLs or else the fetch URLs
+    pub push_urls: Vec<String>,
+    ///The refspecs fetched by default, such as `+refs/heads/*:refs/remotes/origin/*`
+    pub fetch_refspecs: Vec<String>,
+    ///The refspecs pushed by default, empty if `push.default` applies
+    pub push_refspecs: Vec<String>,
+}
+
+pub(crate) fn rename_args<'a>(old_name: &'a str, new_name: &'a str) -> [&'a str; 5] {
+    ["remote", "rename", "--", old_name, new_name]
+}
+
+pub(crate) fn remove_args(name: &str) -> [&str; 4] {
+    ["remote", "remove", "--", name]
+}
+
+pub(crate) fn set_url_args<'a>(name: &'a str, url: &'a str, push: bool) -> Vec<&'a str> {
+    let mut args = vec!["remote", "set-url"];
+    if push {
+        args.push("--push");
+    }
+    args.extend(["--", name, url]);
+    args
+}
+
+pub(crate) fn prune_args(name: &str, dry_run: bool) -> Vec<&str> {
+    let mut args = vec!["remote", "prune"];
+    if dry_run {
+        args.push("--dry-run");
+    }
+    args.extend(["--", name]);
+    args
+}
+
+pub(crate) fn set_head_args<'a>(name: &'a str, head: &'a RemoteHead) -> Vec<&'a str> {
+    let mut args = vec!["remote", "set-head"];
+    match head {
+        RemoteHead::Auto => args.push("--auto"),
+        RemoteHead::Delete => args.push("--delete"),
+        RemoteHead::Branch(_) => {}
+    }
+    args.extend(["--", name]);
+    if let RemoteHead::Branch(branch) = head {
+        args.push(branch);
+    }
+    args
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the output of `git remote -v`, where each line is the name, a tab, then a URL followed
+///by `(fetch)` or `(push)`
+pub(crate) fn parse_remotes(output: &str) -> Result<Vec<Remote>> {
+    let mut remotes: Vec<Remote> = Vec::new();
+    for line in output.lines() {
+        let (name, rest) = line.split_once('\t').ok_or_else(|| unexpected(line))?;
+        if remotes.last().map(|r| r.name.as_str()) != Some(name) {
+            remotes.push(Remote {
+                name: name.to_owned(),
+                fetch_url: None,
+                push_urls: Vec::new(),
+                fetch_refspecs: Vec::new(),
+                push_refspecs: Vec::new(),
+            });
+        }
+
+        let remote = remotes.last_mut().unwrap();
+        if let Some(url) = rest.strip_suffix(" (fetch)") {
+            remote.fetch_url = Some(url.to_owned());
+        } else if let Some(url) = rest.strip_suffix(" (push)") {
+            remote.push_urls.push(url.to_owned());
+        } else {
+            return Err(unexpected(line));
+        }
+    }
+    Ok(remotes)
+}
+
+///Fill in the refspecs of `remotes` from the NUL terminated keys and values printed by
+///`git config -z --get-regexp` with `REFSPECS_ARGS`
+pub(crate) fn apply_refspecs(remotes: &mut [Remote], entries: &[String]) -> Result<()> {
+    for entry in entries {
+        let (key, refspec) = entry.split_once('\n').ok_or_else(|| unexpected(entry))?;
+        //Names may contain dots, the variable name never does
+        let (name, variable) = key
+            .strip_prefix("remote.")
+            .and_then(|key| key.rsplit_once('.'))
+            .ok_or_else(|| unexpected(entry))?;
+        let remote = match remotes.iter_mut().find(|r| r.name == name) {
+            Some(remote) => remote,
+            None => continue,
+        };
+        match variable {
+            "fetch" => remote.fetch_refspecs.push(refspec.to_owned()),
+            "push" => remote.push_refspecs.push(refspec.to_owned()),
+            _ => return Err(unexpected(entry)),
+        }
+    }
+    Ok(())
+}
+
+///Parse the output of `git remote prune`, returning the remote-tracking branches pruned, or
+///which would be on a dry run
+pub(crate) fn parse_pruned(output: &str) -> Result<Vec<String>> {
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix(" * [pruned] ")
+                .or_else(|| line.strip_prefix(" * [would prune] "))
+        })
+        .map(|branch| branch.to_owned())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            vec![
+                "remote",
+                "set-url",
+                "--push",
+                "--",
+                "origin",
+                "ssh://host/repo.git"
+            ],
+            set_url_args("origin", "ssh://host/repo.git", true)
+        );
+        assert_eq!(
+            vec!["remote", "prune", "--dry-run", "--", "origin"],
+            prune_args("origin", true)
+        );
+        assert_eq!(
+            vec!["remote", "set-head", "--", "origin", "main"],
+            set_head_args("origin", &RemoteHead::Branch(String::from("main")))
+        );
+        assert_eq!(
+            vec!["remote", "set-head", "--auto", "--", "origin"],
+            set_head_args("origin", &RemoteHead::Auto)
+        );
+    }
+
+    #[test]
+    fn test_parse_remotes() {
+        let output = concat!(
+            "origin\thttps://example.com/repo.git (fetch)\n",
+            "origin\thttps://example.com/repo.git (push)\n",
+            "origin\thttps://mirror.example.com/repo.git (push)\n",
+            "my.fork\t/srv/my repo (fetch)\n",
+            "my.fork\tssh://host/fork.git (push)\n",
+        );
+        let refspecs = vec![
+            String::from("remote.origin.fetch\n+refs/heads/*:refs/remotes/origin/*"),
+            String::from("remote.my.fork.fetch\n+refs/heads/main:refs/remotes/my.fork/main"),
+            String::from("remote.my.fork.push\nrefs/heads/main:refs/heads/main"),
+            String::from("remote.gone.fetch\n+refs/heads/*:refs/remotes/gone/*"),
+        ];
+
+        let mut remotes = parse_remotes(output).unwrap();
+        apply_refspecs(&mut remotes, &refspecs).unwrap();
+
+        assert_eq!(
+            vec![
+                Remote {
+                    name: String::from("origin"),
+                    fetch_url: Some(String::from("https://example.com/repo.git")),
+                    push_urls: vec![
+                        String::from("https://example.com/repo.git"),
+                        String::from("https://mirror.example.com/repo.git")
+                    ],
+                    fetch_refspecs: vec![String::from("+refs/heads/*:refs/remotes/origin/*")],
+                    push_refspecs: Vec::new(),
+                },
+                Remote {
+                    name: String::from("my.fork"),
+                    fetch_url: Some(String::from("/srv/my repo")),
+                    push_urls: vec![String::from("ssh://host/fork.git")],
+                    fetch_refspecs: vec![String::from(
+                        "+refs/heads/main:refs/remotes/my.fork/main"
+                    )],
+                    push_refspecs: vec![String::from("refs/heads/main:refs/heads/main")],
+                },
+            ],
+            remotes
+        );
+        assert!(parse_remotes("").unwrap().is_empty());
+        assert!(parse_remotes("origin\thttps://example.com/repo.git\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_pruned() {
+        let output = concat!(
+            "Pruning origin\n",
+            "URL: /srv/repo\n",
+            " * [pruned] origin/feature\n",
+            " * [would prune] origin/old\n",
+        );
+
+        assert_eq!(
+            vec!["origin/feature", "origin/old"],
+            parse_pruned(output).unwrap()
+        );
+    }
+}
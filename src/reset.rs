@@ -0,0 +1,110 @@
+///Resets the index and working tree to `HEAD`, or to an empty tree if the branch has no commits
+pub(crate) const HARD_RESET_ARGS: [&str; 2] = ["reset", "--hard"];
+///Removes untracked files and directories, keeping ignored ones and nested repositories
+pub(crate) const CLEAN_ARGS: [&str; 4] = ["clean", "--force", "-d", "--quiet"];
+
+///What `Repository::reset` changes besides moving the current branch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResetMode {
+    ///Leave the index and working tree alone, so the changes since the target are staged
+    Soft,
+    ///Reset the index but not the working tree, so the changes since the target are unstaged
+    #[default]
+    Mixed,
+    ///Reset the index and working tree, discarding all changes to tracked files
+    Hard,
+    ///Reset the index and working tree, failing rather than discarding uncommitted changes to
+    ///files which differ between `HEAD` and the target
+    Keep,
+    ///Reset the index and working tree like `Keep`, but discard unmerged entries, as when
+    ///abandoning a merge
+    Merge,
+}
+
+impl ResetMode {
+    fn arg(self) -> &'static str {
+        match self {
+            ResetMode::Soft => "--soft",
+            ResetMode::Mixed => "--mixed",
+            ResetMode::Hard => "--hard",
+            ResetMode::Keep => "--keep",
+            ResetMode::Merge => "--merge",
+        }
+    }
+}
+
+///Arguments resetting to the target, with a trailing `--` so it is never taken as a path
+pub(crate) fn reset_args(mode: ResetMode, target: &str) -> [&str; 4] {
+    ["reset", mode.arg(), target, "--"]
+}
+
+///Arguments restoring paths from `source`, or the index if not given, with git restoring only
+///the working tree when neither `staged` nor `worktree` is given
+pub(crate) fn restore_args(
+    pathspecs: Vec<&str>,
+    source: Option<&str>,
+    staged: bool,
+    worktree: bool,
+) -> Vec<String> {
+    let mut args = vec![String::from("restore")];
+    if let Some(source) = source {
+        args.push(format!("--source={}", source));
+    }
+    if staged {
+        args.push(String::from("--staged"));
+    }
+    if worktree {
+        args.push(String::from("--worktree"));
+    }
+    args.push(String::from("--"));
+    args.extend(pathspecs.into_iter().map(String::from));
+    args
+}
+
+pub(crate) fn checkout_paths_args<'a>(
+    tree_ish: Option<&'a str>,
+    pathspecs: Vec<&'a str>,
+) -> Vec<&'a str> {
+    let mut args = vec!["checkout"];
+    args.extend(tree_ish);
+    args.push("--");
+    args.extend(pathspecs);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            ["reset", "--keep", "HEAD~1", "--"],
+            reset_args(ResetMode::Keep, "HEAD~1")
+        );
+        assert_eq!(
+            vec![
+                "restore",
+                "--source=main",
+                "--staged",
+                "--worktree",
+                "--",
+                "src",
+                "-f"
+            ],
+            restore_args(vec!["src", "-f"], Some("main"), true, true)
+        );
+        assert_eq!(
+            vec!["restore", "--", "."],
+            restore_args(vec!["."], None, false, false)
+        );
+        assert_eq!(
+            vec!["checkout", "v1.0", "--", "Cargo.toml"],
+            checkout_paths_args(Some("v1.0"), vec!["Cargo.toml"])
+        );
+        assert_eq!(
+            vec!["checkout", "--", "Cargo.toml"],
+            checkout_paths_args(None, vec!["Cargo.toml"])
+        );
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::VecDeque;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+///How often a running git process is checked for a timeout or cancellation
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+///Aborts in-flight git commands when cancelled, from any thread
+///
+///Clones share the same state, so a token can be handed to a `Repository` and cancelled
+///elsewhere. Once cancelled a token stays cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    ///Kill any git command currently running with this token, and any started later
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &CancellationToken) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+///A single execution of git, as passed to a `GitRunner`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    ///The git executable, `git` unless configured otherwise
+    pub program: OsString,
+    ///The directory git is run in
+    pub cwd: PathBuf,
+    ///The arguments passed to git, not including the executable itself
+    pub args: Vec<OsString>,
+    ///Environment variables set in addition to those inherited
+    pub envs: Vec<(OsString, OsString)>,
+    ///Inherited environment variables removed before running git
+    pub env_remove: Vec<OsString>,
+    ///Whether to run git without inheriting any environment variables
+    pub env_clear: bool,
+    ///Data written to git's stdin
+    pub input: Option<Vec<u8>>,
+    ///How long git may run before it is killed
+    pub timeout: Option<Duration>,
+    ///Kills git when cancelled
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Invocation {
+    pub fn new<P, I, S>(cwd: P, args: I) -> Invocation
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Invocation {
+            program: OsString::from("git"),
+            cwd: cwd.as_ref().to_path_buf(),
+            args: args.into_iter().map(|a| a.as_ref().to_owned()).collect(),
+            envs: Vec::new(),
+            env_remove: Vec::new(),
+            env_clear: false,
+            input: None,
+            timeout: None,
+            cancellation: None,
+        }
+    }
+
+    ///Whether git may need to be killed before it exits by itself
+    pub(crate) fn is_interruptible(&self) -> bool {
+        self.timeout.is_some() || self.cancellation.is_some()
+    }
+
+    ///Whether the invocation has been cancelled or has run past its timeout
+    pub(crate) fn interruption(&self, started: Instant) -> Option<Interruption> {
+        if self.cancellation.as_ref().is_some_and(|c| c.is_cancelled()) {
+            Some(Interruption::Cancelled)
+        } else if self.timeout.is_some_and(|t| started.elapsed() >= t) {
+            Some(Interruption::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    ///A command for the invocation, with stdio left for the caller to configure
+    pub(crate) fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        if self.env_clear {
+            command.env_clear();
+        }
+        for key in &self.env_remove {
+            command.env_remove(key);
+        }
+        command
+            .current_dir(&self.cwd)
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)));
+        #[cfg(unix)]
+        if self.is_interruptible() {
+            use std::os::unix::process::CommandExt;
+            //Run in a new process group so helpers spawned by git are killed along with it
+            command.process_group(0);
+        }
+        command
+    }
+
+    ///The arguments as lossily converted strings, convenient for assertions
+    pub fn args_lossy(&self) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    ///The invocation as it would be typed into a shell, used in error messages
+    pub fn command_line(&self) -> String {
+        let mut line = self.program.to_string_lossy().into_owned();
+        for arg in self.args_lossy() {
+            line.push(' ');
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                line.push_str(&format!("'{}'", arg));
+            } else {
+                line.push_str(&arg);
+            }
+        }
+        line
+    }
+}
+
+///Why git was killed before it exited by itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interruption {
+    TimedOut,
+    Cancelled,
+}
+
+///The raw result of a git invocation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitOutput {
+    ///The exit code, `None` if git was terminated by a signal
+    pub exit_code: Option<i32>,
+    ///Everything written to stdout, or as much as was read before git was killed
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    ///Set if git was killed due to a timeout or cancellation
+    pub interrupted: Option<Interruption>,
+}
+
+impl GitOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && self.interrupted.is_none()
+    }
+}
+
+///Kill a process along with the process group it leads, if any
+pub(crate) fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    //SAFETY: kill has no memory safety requirements, a stale pid at worst results in ESRCH
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+///Executes git on behalf of a `Repository`
+///
+///The default implementation, `ProcessRunner`, spawns a `git` process. Alternative
+///implementations can be supplied through `Repository::with_runner`, for example to
+///instrument or mock git invocations. An error returned by a runner is passed on as the
+///source of `GitError::Execution`.
+pub trait GitRunner: Send + Sync {
+    fn run(&self, invocation: &Invocation) -> io::Result<GitOutput>;
+
+    ///Run git, passing stderr to `on_stderr` as it is written
+    ///
+    ///Used to report progress. The default implementation passes all of stderr at once
+    ///after git exits.
+    fn run_streaming(
+        &self,
+        invocation: &Invocation,
+        on_stderr: &mut dyn FnMut(&[u8]),
+    ) -> io::Result<GitOutput> {
+        let output = self.run(invocation)?;
+        on_stderr(&output.stderr);
+        Ok(output)
+    }
+}
+
+impl<R: GitRunner + ?Sized> GitRunner for Arc<R> {
+    fn run(&self, invocation: &Invocation) -> io::Result<GitOutput> {
+        (**self).run(invocation)
+    }
+
+    fn run_streaming(
+        &self,
+        invocation: &Invocation,
+        on_stderr: &mut dyn FnMut(&[u8]),
+    ) -> io::Result<GitOutput> {
+        (**self).run_streaming(invocation, on_stderr)
+    }
+}
+
+///Runs git as a child process
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessRunner;
+
+impl GitRunner for ProcessRunner {
+    fn run(&self, invocation: &Invocation) -> io::Result<GitOutput> {
+        if invocation.is_interruptible() {
+            return run_monitored(invocation, &mut |_| {});
+        }
+
+        let mut command = invocation.command();
+        let output = match &invocation.input {
+            Some(input) => command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .and_then(|mut child| {
+                    //Written from another thread so git can't block on a full stdout pipe
+                    //while we block on a full stdin one
+                    if let Some(mut stdin) = child.stdin.take() {
+                        let input = input.clone();
+                        thread::spawn(move || stdin.write_all(&input));
+                    }
+                    child.wait_with_output()
+                }),
+            None => command.output(),
+        }?;
+
+        Ok(GitOutput {
+            exit_code: output.status.code(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+            interrupted: None,
+        })
+    }
+
+    fn run_streaming(
+        &self,
+        invocation: &Invocation,
+        on_stderr: &mut dyn FnMut(&[u8]),
+    ) -> io::Result<GitOutput> {
+        run_monitored(invocation, on_stderr)
+    }
+}
+
+type SharedBuffer = Arc<Mutex<Vec<u8>>>;
+
+///Read a pipe to completion on another thread, so its contents are available even if
+///reading never finishes. Each chunk read is also sent to `chunks`, if given.
+fn collect<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    chunks: Option<mpsc::Sender<Vec<u8>>>,
+) -> (SharedBuffer, JoinHandle<()>) {
+    let buffer = SharedBuffer::default();
+    let shared = buffer.clone();
+    let handle = thread::spawn(move || {
+        if let Some(mut pipe) = pipe {
+            let mut chunk = [0; 8192];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                shared.lock().unwrap().extend_from_slice(&chunk[..n]);
+                if let Some(chunks) = &chunks {
+                    let _ = chunks.send(chunk[..n].to_vec());
+                }
+            }
+        }
+    });
+    (buffer, handle)
+}
+
+///Output for an invocation whose token was cancelled before git was started
+pub(crate) fn cancelled_before_start(invocation: &Invocation) -> Option<GitOutput> {
+    invocation
+        .cancellation
+        .as_ref()
+        .filter(|c| c.is_cancelled())
+        .map(|_| GitOutput {
+            interrupted: Some(Interruption::Cancelled),
+            ..GitOutput::default()
+        })
+}
+
+///Run git, checking for timeouts and cancellation and passing on stderr as it is written
+fn run_monitored(
+    invocation: &Invocation,
+    on_stderr: &mut dyn FnMut(&[u8]),
+) -> io::Result<GitOutput> {
+    if let Some(output) = cancelled_before_start(invocation) {
+        return Ok(output);
+    }
+
+    let mut child = invocation
+        .command()
+        .stdin(if invocation.input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let started = Instant::now();
+
+    if let (Some(input), Some(mut stdin)) = (invocation.input.clone(), child.stdin.take()) {
+        thread::spawn(move || stdin.write_all(&input));
+    }
+    let (sender, chunks) = mpsc::channel();
+    let (stdout, stdout_reader) = collect(child.stdout.take(), None);
+    let (stderr, stderr_reader) = collect(child.stderr.take(), Some(sender));
+
+    let (status, interrupted) = loop {
+        match chunks.recv_timeout(POLL_INTERVAL) {
+            Ok(chunk) => on_stderr(&chunk),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => thread::sleep(POLL_INTERVAL),
+        }
+        if let Some(status) = child.try_wait()? {
+            break (Some(status), None);
+        }
+        if let Some(interruption) = invocation.interruption(started) {
+            kill(&mut child);
+            child.wait()?;
+            break (None, Some(interruption));
+        }
+    };
+
+    //A process that escaped the group may still hold the pipes open, so only wait for
+    //the readers if git exited by itself
+    if interrupted.is_none() {
+        let _ = stdout_reader.join();
+        let _ = stderr_reader.join();
+    }
+    for chunk in chunks.try_iter() {
+        on_stderr(&chunk);
+    }
+    let stdout = stdout.lock().unwrap().clone();
+    let stderr = stderr.lock().unwrap().clone();
+
+    Ok(GitOutput {
+        exit_code: status.and_then(|s| s.code()),
+        stdout,
+        stderr,
+        interrupted,
+    })
+}
+
+fn kill(child: &mut Child) {
+    kill_process_group(child.id());
+    let _ = child.kill();
+}
+
+///Records every invocation before passing it on to another runner
+#[derive(Debug, Default)]
+pub struct RecordingRunner<R = ProcessRunner> {
+    inner: R,
+    invocations: Mutex<Vec<Invocation>>,
+}
+
+impl RecordingRunner<ProcessRunner> {
+    pub fn new() -> RecordingRunner<ProcessRunner> {
+        RecordingRunner::wrapping(ProcessRunner)
+    }
+}
+
+impl<R: GitRunner> RecordingRunner<R> {
+    pub fn wrapping(inner: R) -> RecordingRunner<R> {
+        RecordingRunner {
+            inner,
+            invocations: Mutex::new(Vec::new()),
+        }
+    }
+
+    ///All invocations made so far, oldest first
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.lock().unwrap().clone()
+    }
+}
+
+impl<R: GitRunner> GitRunner for RecordingRunner<R> {
+    fn run(&self, invocation: &Invocation) -> io::Result<GitOutput> {
+        self.invocations.lock().unwrap().push(invocation.clone());
+        self.inner.run(invocation)
+    }
+
+    fn run_streaming(
+        &self,
+        invocation: &Invocation,
+        on_stderr: &mut dyn FnMut(&[u8]),
+    ) -> io::Result<GitOutput> {
+        self.invocations.lock().unwrap().push(invocation.clone());
+        self.inner.run_streaming(invocation, on_stderr)
+    }
+}
+
+///Returns canned outputs in order, without running git
+///
+///Every invocation is recorded. Once the script is exhausted further invocations fail.
+#[derive(Debug, Default)]
+pub struct ScriptedRunner {
+    outputs: Mutex<VecDeque<GitOutput>>,
+    invocations: Mutex<Vec<Invocation>>,
+}
+
+impl ScriptedRunner {
+    pub fn new() -> ScriptedRunner {
+        ScriptedRunner::default()
+    }
+
+    ///Queue an output to be returned by the next unanswered invocation
+    pub fn push(&self, output: GitOutput) -> &Self {
+        self.outputs.lock().unwrap().push_back(output);
+        self
+    }
+
+    ///Queue a successful invocation printing the given stdout
+    pub fn push_success(&self, stdout: &str) -> &Self {
+        self.push(GitOutput {
+            exit_code: Some(0),
+            stdout: stdout.as_bytes().to_vec(),
+            ..GitOutput::default()
+        })
+    }
+
+    ///Queue a failed invocation with the given exit code and stderr
+    pub fn push_failure(&self, exit_code: i32, stderr: &str) -> &Self {
+        self.push(GitOutput {
+            exit_code: Some(exit_code),
+            stderr: stderr.as_bytes().to_vec(),
+            ..GitOutput::default()
+        })
+    }
+
+    ///All invocations made so far, oldest first
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.lock().unwrap().clone()
+    }
+}
+
+impl GitRunner for ScriptedRunner {
+    fn run(&self, invocation: &Invocation) -> io::Result<GitOutput> {
+        self.invocations.lock().unwrap().push(invocation.clone());
+        self.outputs.lock().unwrap().pop_front().ok_or_else(|| {
+            io::Error::other(format!(
+                "no scripted output for {}",
+                invocation.command_line()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let invocation = Invocation::new("/repo", ["commit", "-m", "some message", ""]);
+
+        assert_eq!("git commit -m 'some message' ''", invocation.command_line());
+    }
+
+    #[test]
+    fn test_cancellation_token_is_shared() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, CancellationToken::new());
+    }
+
+    #[test]
+    fn test_interrupted_output_is_not_success() {
+        let output = GitOutput {
+            exit_code: Some(0),
+            interrupted: Some(Interruption::Cancelled),
+            ..GitOutput::default()
+        };
+
+        assert!(!output.success());
+    }
+
+    #[test]
+    fn test_scripted_runner() {
+        let runner = ScriptedRunner::new();
+        runner
+            .push_success("main\n")
+            .push_failure(128, "fatal: oops\n");
+
+        let first = runner.run(&Invocation::new("/repo", ["branch"])).unwrap();
+        let second = runner.run(&Invocation::new("/repo", ["fetch"])).unwrap();
+        let third = runner.run(&Invocation::new("/repo", ["push"]));
+
+        assert!(first.success());
+        assert_eq!(b"main\n".to_vec(), first.stdout);
+        assert!(!second.success());
+        assert!(third.is_err());
+        assert_eq!(
+            vec![vec!["branch"], vec!["fetch"], vec!["push"]],
+            runner
+                .invocations()
+                .iter()
+                .map(|i| i.args_lossy())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use super::GitError;
+use crate::merge::Conflict;
+use crate::types::Result;
+
+///Resolves the most recent stash, printing nothing if there are none
+pub(crate) const HEAD_ARGS: [&str; 3] = ["for-each-ref", "--format=%(objectname)", "refs/stash"];
+pub(crate) const LIST_ARGS: [&str; 3] = ["stash", "list", "--format=%gd%x00%H%x00%gs"];
+
+///Options for `Repository::stash_push`
+#[derive(Debug, Clone, Default)]
+pub struct StashOptions {
+    message: Option<String>,
+    paths: Vec<String>,
+    keep_index: bool,
+    include_untracked: bool,
+}
+
+impl StashOptions {
+    ///Stash all changes to tracked files
+    pub fn new() -> StashOptions {
+        StashOptions::default()
+    }
+
+    ///Describe the stash with the given message rather than the commit it was based on
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    ///Only stash changes to paths matching the given pathspec
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_owned());
+        self
+    }
+
+    ///Leave changes already in the index in place, stashing only unstaged changes
+    pub fn keep_index(mut self) -> Self {
+        self.keep_index = true;
+        self
+    }
+
+    ///Stash untracked files as well, removing them from the working tree
+    pub fn include_untracked(mut self) -> Self {
+        self.include_untracked = true;
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("stash"), String::from("push")];
+        if self.keep_index {
+            args.push(String::from("--keep-index"));
+        }
+        if self.include_untracked {
+            args.push(String::from("--include-untracked"));
+        }
+        if let Some(message) = &self.message {
+            args.push(format!("--message={}", message));
+        }
+        args.push(String::from("--"));
+        args.extend(self.paths.iter().cloned());
+        args
+    }
+}
+
+pub(crate) fn reference(index: usize) -> String {
+    format!("stash@{{{}}}", index)
+}
+
+pub(crate) fn show_args(index: usize) -> Vec<String> {
+    vec![
+        String::from("stash"),
+        String::from("show"),
+        String::from("--no-color"),
+        String::from("--no-ext-diff"),
+        String::from("--no-textconv"),
+        String::from("-z"),
+        String::from("--raw"),
+        String::from("--patch"),
+        String::from("--src-prefix=a/"),
+        String::from("--dst-prefix=b/"),
+        reference(index),
+    ]
+}
+
+///A stash as returned by `Repository::stash_list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StashEntry {
+    ///The position in the stash list, `0` being the most recent
+    pub index: usize,
+    ///The branch the changes were stashed from, `None` if `HEAD` was detached
+    pub branch: Option<String>,
+    ///The message given when stashing, or the commit the stash was based on if there wasn't one
+    pub message: String,
+    ///The hash of the stash commit
+    pub hash: String,
+}
+
+///The result of applying or popping a stash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StashApplyOutcome {
+    Applied,
+    ///The stashed changes conflict with `HEAD`, and are left to be resolved. A popped stash is
+    ///kept rather than dropped.
+    Conflicted {
+        conflicts: Vec<Conflict>,
+    },
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the output of `git stash list` using the `LIST_ARGS` format
+pub(crate) fn parse_stashes(output: &str) -> Result<Vec<StashEntry>> {
+    output
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\0').collect();
+            if fields.len() != 3 {
+                return Err(unexpected(line));
+            }
+
+            let index = fields[0]
+                .strip_prefix("stash@{")
+                .and_then(|index| index.strip_suffix('}'))
+                .and_then(|index| index.parse().ok())
+                .ok_or_else(|| unexpected(line))?;
+            //The reflog subject is `WIP on <branch>: <commit>` or `On <branch>: <message>`
+            let (branch, message) = fields[2]
+                .strip_prefix("WIP on ")
+                .or_else(|| fields[2].strip_prefix("On "))
+                .and_then(|subject| subject.split_once(": "))
+                .ok_or_else(|| unexpected(line))?;
+
+            Ok(StashEntry {
+                index,
+                branch: Some(branch.to_owned()).filter(|b| b != "(no branch)"),
+                message: message.to_owned(),
+                hash: fields[1].to_owned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let options = StashOptions::new()
+            .message("Before linting")
+            .path("src")
+            .keep_index()
+            .include_untracked();
+
+        assert_eq!(
+            vec![
+                "stash",
+                "push",
+                "--keep-index",
+                "--include-untracked",
+                "--message=Before linting",
+                "--",
+                "src"
+            ],
+            options.args()
+        );
+        assert_eq!(vec!["stash", "push", "--"], StashOptions::new().args());
+        assert_eq!("stash@{2}", show_args(2)[10]);
+    }
+
+    #[test]
+    fn test_parse_stashes() {
+        let output = concat!(
+            "stash@{0}\0aaaa\0WIP on (no branch): 1234567 Some commit\n",
+            "stash@{1}\0bbbb\0On feature: Before linting\n",
+        );
+
+        let stashes = parse_stashes(output).unwrap();
+
+        assert_eq!(
+            vec![
+                StashEntry {
+                    index: 0,
+                    branch: None,
+                    message: String::from("1234567 Some commit"),
+                    hash: String::from("aaaa"),
+                },
+                StashEntry {
+                    index: 1,
+                    branch: Some(String::from("feature")),
+                    message: String::from("Before linting"),
+                    hash: String::from("bbbb"),
+                },
+            ],
+            stashes
+        );
+        assert!(parse_stashes("").unwrap().is_empty());
+        assert!(parse_stashes("stash@{x}\0aaaa\0On main: message\n").is_err());
+    }
+}
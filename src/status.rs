@@ -0,0 +1,277 @@
+use super::GitError;
+use crate::types::Result;
+
+pub(crate) const STATUS_ARGS: [&str; 4] = ["status", "--porcelain=v2", "-z", "--branch"];
+///Also lists ignored files, which can mean walking large directories such as `target/`
+pub(crate) const IGNORED_STATUS_ARGS: [&str; 5] =
+    ["status", "--porcelain=v2", "-z", "--branch", "--ignored"];
+
+///The state of a path in either the index or the working tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    UpdatedButUnmerged,
+}
+
+impl FileState {
+    fn from_code(code: char) -> Result<FileState> {
+        match code {
+            '.' => Ok(FileState::Unmodified),
+            'M' => Ok(FileState::Modified),
+            'T' => Ok(FileState::TypeChanged),
+            'A' => Ok(FileState::Added),
+            'D' => Ok(FileState::Deleted),
+            'R' => Ok(FileState::Renamed),
+            'C' => Ok(FileState::Copied),
+            'U' => Ok(FileState::UpdatedButUnmerged),
+            _ => Err(unexpected(&code.to_string())),
+        }
+    }
+}
+
+///A tracked path with changes in the index and/or the working tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    pub path: String,
+    ///The path this entry was renamed or copied from, if any
+    pub original_path: Option<String>,
+    pub index: FileState,
+    pub worktree: FileState,
+}
+
+///A path with an unresolved merge conflict
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmergedEntry {
+    pub path: String,
+    pub ours: FileState,
+    pub theirs: FileState,
+}
+
+///Information about the currently checked out branch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchStatus {
+    ///The commit at `HEAD`, `None` if there are no commits yet
+    pub oid: Option<String>,
+    ///The name of the current branch, `None` if `HEAD` is detached
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+}
+
+///The state of the working tree, as reported by `git status`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub branch: BranchStatus,
+    pub entries: Vec<StatusEntry>,
+    pub unmerged: Vec<UnmergedEntry>,
+    pub untracked: Vec<String>,
+    ///Empty unless requested with `Repository::status_with_ignored`
+    pub ignored: Vec<String>,
+}
+
+impl Status {
+    ///Returns true if there are no changes, conflicts or untracked files
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty() && self.unmerged.is_empty() && self.untracked.is_empty()
+    }
+
+    ///Paths newly added to the index
+    pub fn added(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.index == FileState::Added)
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    ///Paths modified in either the index or the working tree
+    pub fn modified(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry.index == FileState::Modified || entry.worktree == FileState::Modified
+            })
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+fn parse_xy(xy: &str) -> Result<(FileState, FileState)> {
+    let mut chars = xy.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(x), Some(y), None) => Ok((FileState::from_code(x)?, FileState::from_code(y)?)),
+        _ => Err(unexpected(xy)),
+    }
+}
+
+fn parse_header(header: &str, branch: &mut BranchStatus) -> Result<()> {
+    let mut parts = header.splitn(2, ' ');
+    let key = parts.next().unwrap_or_default();
+    let value = parts.next().ok_or_else(|| unexpected(header))?;
+    match key {
+        "branch.oid" if value != "(initial)" => branch.oid = Some(value.to_owned()),
+        "branch.head" if value != "(detached)" => branch.head = Some(value.to_owned()),
+        "branch.upstream" => branch.upstream = Some(value.to_owned()),
+        "branch.ab" => {
+            let mut counts = value.split(' ');
+            let ahead = counts.next().and_then(|a| a.strip_prefix('+'));
+            let behind = counts.next().and_then(|b| b.strip_prefix('-'));
+            match (ahead, behind) {
+                (Some(ahead), Some(behind)) => {
+                    branch.ahead = Some(ahead.parse().map_err(|_| unexpected(header))?);
+                    branch.behind = Some(behind.parse().map_err(|_| unexpected(header))?);
+                }
+                _ => return Err(unexpected(header)),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+///Parse the output of `git status --porcelain=v2 -z --branch`
+pub(crate) fn parse_status(output: &str) -> Result<Status> {
+    let mut status = Status::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+
+    while let Some(record) = records.next() {
+        let (kind, rest) = record.split_at(record.find(' ').ok_or_else(|| unexpected(record))?);
+        let rest = &rest[1..];
+        match kind {
+            "#" => parse_header(rest, &mut status.branch)?,
+            "1" => {
+                let fields: Vec<&str> = rest.splitn(8, ' ').collect();
+                if fields.len() != 8 {
+                    return Err(unexpected(record));
+                }
+                let (index, worktree) = parse_xy(fields[0])?;
+                status.entries.push(StatusEntry {
+                    path: fields[7].to_owned(),
+                    original_path: None,
+                    index,
+                    worktree,
+                });
+            }
+            "2" => {
+                let fields: Vec<&str> = rest.splitn(9, ' ').collect();
+                if fields.len() != 9 {
+                    return Err(unexpected(record));
+                }
+                let (index, worktree) = parse_xy(fields[0])?;
+                let original_path = records.next().ok_or_else(|| unexpected(record))?;
+                status.entries.push(StatusEntry {
+                    path: fields[8].to_owned(),
+                    original_path: Some(original_path.to_owned()),
+                    index,
+                    worktree,
+                });
+            }
+            "u" => {
+                let fields: Vec<&str> = rest.splitn(10, ' ').collect();
+                if fields.len() != 10 {
+                    return Err(unexpected(record));
+                }
+                let (ours, theirs) = parse_xy(fields[0])?;
+                status.unmerged.push(UnmergedEntry {
+                    path: fields[9].to_owned(),
+                    ours,
+                    theirs,
+                });
+            }
+            "?" => status.untracked.push(rest.to_owned()),
+            "!" => status.ignored.push(rest.to_owned()),
+            _ => return Err(unexpected(record)),
+        }
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_branch_headers() {
+        let output = "# branch.oid 5d1b4a4c1bd1c5e0b0bc2e6d0e3e3a1a0f9e0a11\0# branch.head main\0# branch.upstream origin/main\0# branch.ab +2 -1\0";
+
+        let status = parse_status(output).unwrap();
+
+        assert_eq!(
+            status.branch,
+            BranchStatus {
+                oid: Some(String::from("5d1b4a4c1bd1c5e0b0bc2e6d0e3e3a1a0f9e0a11")),
+                head: Some(String::from("main")),
+                upstream: Some(String::from("origin/main")),
+                ahead: Some(2),
+                behind: Some(1),
+            }
+        );
+        assert!(status.is_clean());
+    }
+
+    #[test]
+    fn test_parse_initial_detached() {
+        let status = parse_status("# branch.oid (initial)\0# branch.head (detached)\0").unwrap();
+
+        assert_eq!(status.branch, BranchStatus::default());
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let output = concat!(
+            "1 M. N... 100644 100644 100644 aaaa bbbb staged file.txt\0",
+            "2 R. N... 100644 100644 100644 aaaa aaaa R100 new name\0old name\0",
+            "u UU N... 100644 100644 100644 100644 aaaa bbbb cccc conflicted\0",
+            "? untracked file\0",
+            "! target/\0",
+        );
+
+        let status = parse_status(output).unwrap();
+
+        assert_eq!(
+            status.entries,
+            vec![
+                StatusEntry {
+                    path: String::from("staged file.txt"),
+                    original_path: None,
+                    index: FileState::Modified,
+                    worktree: FileState::Unmodified,
+                },
+                StatusEntry {
+                    path: String::from("new name"),
+                    original_path: Some(String::from("old name")),
+                    index: FileState::Renamed,
+                    worktree: FileState::Unmodified,
+                },
+            ]
+        );
+        assert_eq!(
+            status.unmerged,
+            vec![UnmergedEntry {
+                path: String::from("conflicted"),
+                ours: FileState::UpdatedButUnmerged,
+                theirs: FileState::UpdatedButUnmerged,
+            }]
+        );
+        assert_eq!(status.untracked, vec!["untracked file"]);
+        assert_eq!(status.ignored, vec!["target/"]);
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert!(parse_status("1 M. N...\0").is_err());
+        assert!(parse_status("x something\0").is_err());
+    }
+}
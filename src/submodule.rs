@@ -0,0 +1,336 @@
+use super::GitError;
+use crate::types::Result;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+
+pub(crate) const GITMODULES: &str = ".gitmodules";
+pub(crate) const CONFIG_ARGS: [&str; 5] = ["config", "--file", GITMODULES, "-z", "--list"];
+pub(crate) const STATUS_ARGS: [&str; 2] = ["submodule", "status"];
+pub(crate) const GITLINKS_ARGS: [&str; 3] = ["ls-files", "--stage", "-z"];
+
+///Options for `Repository::submodule_update`
+#[derive(Debug, Clone, Default)]
+pub struct SubmoduleUpdateOptions {
+    init: bool,
+    recursive: bool,
+    remote: bool,
+    depth: Option<u32>,
+    paths: Vec<String>,
+}
+
+impl SubmoduleUpdateOptions {
+    ///Check out the recorded commit of every initialised submodule
+    pub fn new() -> SubmoduleUpdateOptions {
+        SubmoduleUpdateOptions::default()
+    }
+
+    ///Initialise any submodules which haven't been yet
+    pub fn init(mut self) -> Self {
+        self.init = true;
+        self
+    }
+
+    ///Update submodules of submodules as well
+    pub fn recursive(mut self) -> Self {
+        self.recursive = true;
+        self
+    }
+
+    ///Check out the tip of each submodule's remote-tracking branch rather than the recorded commit
+    pub fn remote(mut self) -> Self {
+        self.remote = true;
+        self
+    }
+
+    ///Clone missing submodules with history truncated to the given number of commits
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    ///Only update the submodule at the given path
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_owned());
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let mut args = vec![String::from("submodule"), String::from("update")];
+        if self.init {
+            args.push(String::from("--init"));
+        }
+        if self.recursive {
+            args.push(String::from("--recursive"));
+        }
+        if self.remote {
+            args.push(String::from("--remote"));
+        }
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={}", depth));
+        }
+        args.push(String::from("--"));
+        args.extend(self.paths.iter().cloned());
+        args
+    }
+}
+
+pub(crate) fn add_args(url: &str, path: &Path, branch: Option<&str>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["submodule".into(), "add".into()];
+    if let Some(branch) = branch {
+        args.push(format!("--branch={}", branch).into());
+    }
+    args.push("--".into());
+    args.push(url.into());
+    args.push(path.into());
+    args
+}
+
+pub(crate) fn sync_args(recursive: bool) -> Vec<&'static str> {
+    let mut args = vec!["submodule", "sync"];
+    if recursive {
+        args.push("--recursive");
+    }
+    args
+}
+
+pub(crate) fn foreach_args(command: &str, recursive: bool) -> Vec<String> {
+    let mut args = vec![String::from("submodule"), String::from("foreach")];
+    if recursive {
+        args.push(String::from("--recursive"));
+    }
+    args.push(command.to_owned());
+    args
+}
+
+///A submodule as returned by `Repository::submodule_status`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submodule {
+    pub name: String,
+    ///The path of the submodule relative to the root of the repository
+    pub path: String,
+    ///The URL in `.gitmodules`, which may be relative to the superproject's remote
+    pub url: Option<String>,
+    ///The branch followed by `SubmoduleUpdateOptions::remote`, if configured
+    pub branch: Option<String>,
+    ///The commit recorded in the superproject's index, `None` if the path isn't a submodule
+    ///there
+    pub recorded_commit: Option<String>,
+    ///The commit checked out in the submodule, `None` if it isn't initialised
+    pub checked_out_commit: Option<String>,
+    ///Whether the submodule has been initialised with `SubmoduleUpdateOptions::init`
+    pub initialised: bool,
+    ///Whether the submodule has a different commit checked out to the one recorded, or has
+    ///merge conflicts
+    pub modified: bool,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the output of `git config --file .gitmodules -z --list` into submodules in the order
+///they are declared, without their status
+pub(crate) fn parse_gitmodules(output: &str) -> Result<Vec<Submodule>> {
+    let mut submodules: Vec<Submodule> = Vec::new();
+    for entry in output.split_terminator('\0') {
+        let (key, value) = entry.split_once('\n').ok_or_else(|| unexpected(entry))?;
+        //Names may contain dots, the variable name never does
+        let (name, variable) = match key
+            .strip_prefix("submodule.")
+            .and_then(|key| key.rsplit_once('.'))
+        {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        let index = match submodules.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                submodules.push(Submodule {
+                    name: name.to_owned(),
+                    path: String::new(),
+                    url: None,
+                    branch: None,
+                    recorded_commit: None,
+                    checked_out_commit: None,
+                    initialised: false,
+                    modified: false,
+                });
+                submodules.len() - 1
+            }
+        };
+        let submodule = &mut submodules[index];
+        match variable {
+            "path" => submodule.path = value.to_owned(),
+            "url" => submodule.url = Some(value.to_owned()),
+            "branch" => submodule.branch = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+    submodules.retain(|s| !s.path.is_empty());
+    Ok(submodules)
+}
+
+///Parse the output of `git ls-files --stage -z`, returning the commit recorded for each
+///submodule path
+pub(crate) fn parse_gitlinks(output: &str) -> Result<HashMap<String, String>> {
+    let mut gitlinks = HashMap::new();
+    for record in output.split_terminator('\0') {
+        let (info, path) = record.split_once('\t').ok_or_else(|| unexpected(record))?;
+        let fields: Vec<&str> = info.split(' ').collect();
+        if fields.len() != 3 {
+            return Err(unexpected(record));
+        }
+        if fields[0] == "160000" {
+            gitlinks.insert(path.to_owned(), fields[1].to_owned());
+        }
+    }
+    Ok(gitlinks)
+}
+
+///Fill in the status of `submodules` from their recorded commits and the output of
+///`git submodule status`
+///
+///Each status line is a state character, a commit, the path and optionally a description of
+///the commit in brackets. Paths may contain spaces, so lines are matched against the known
+///paths.
+pub(crate) fn apply_status(
+    submodules: &mut [Submodule],
+    gitlinks: &HashMap<String, String>,
+    output: &str,
+) -> Result<()> {
+    for submodule in submodules.iter_mut() {
+        submodule.recorded_commit = gitlinks.get(&submodule.path).cloned();
+    }
+
+    for line in output.lines() {
+        let state = line.chars().next().ok_or_else(|| unexpected(line))?;
+        let (commit, rest) = line[state.len_utf8()..]
+            .split_once(' ')
+            .ok_or_else(|| unexpected(line))?;
+        let submodule = match submodules.iter_mut().find(|s| {
+            rest == s.path
+                || rest
+                    .strip_prefix(s.path.as_str())
+                    .is_some_and(|r| r.starts_with(" ("))
+        }) {
+            Some(submodule) => submodule,
+            None => continue,
+        };
+
+        match state {
+            ' ' => submodule.initialised = true,
+            '-' => {}
+            '+' | 'U' => {
+                submodule.initialised = true;
+                submodule.modified = true;
+            }
+            _ => return Err(unexpected(line)),
+        }
+        if submodule.initialised {
+            submodule.checked_out_commit = Some(commit.to_owned());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_args() {
+        let options = SubmoduleUpdateOptions::new()
+            .init()
+            .recursive()
+            .remote()
+            .depth(1)
+            .path("vendor/lib");
+
+        assert_eq!(
+            vec![
+                "submodule",
+                "update",
+                "--init",
+                "--recursive",
+                "--remote",
+                "--depth=1",
+                "--",
+                "vendor/lib"
+            ],
+            options.args()
+        );
+        assert_eq!(
+            vec![
+                "submodule",
+                "add",
+                "--branch=main",
+                "--",
+                "../lib.git",
+                "vendor/lib"
+            ],
+            add_args("../lib.git", Path::new("vendor/lib"), Some("main"))
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let gitmodules = concat!(
+            "submodule.lib.path\nvendor/lib\0",
+            "submodule.lib.url\n../lib.git\0",
+            "submodule.lib.branch\nmain\0",
+            "submodule.docs.v2.path\nmy docs\0",
+            "submodule.docs.v2.url\nhttps://example.com/docs.git\0",
+            "submodule.new.path\nnew\0",
+        );
+        let gitlinks = concat!(
+            "100644 aaaa 0\t.gitmodules\0",
+            "160000 bbbb 0\tvendor/lib\0",
+            "160000 cccc 0\tmy docs\0",
+        );
+        let status = concat!("+dddd vendor/lib (heads/main)\n", "-cccc my docs\n",);
+
+        let mut submodules = parse_gitmodules(gitmodules).unwrap();
+        let gitlinks = parse_gitlinks(gitlinks).unwrap();
+        apply_status(&mut submodules, &gitlinks, status).unwrap();
+
+        assert_eq!(
+            vec![
+                Submodule {
+                    name: String::from("lib"),
+                    path: String::from("vendor/lib"),
+                    url: Some(String::from("../lib.git")),
+                    branch: Some(String::from("main")),
+                    recorded_commit: Some(String::from("bbbb")),
+                    checked_out_commit: Some(String::from("dddd")),
+                    initialised: true,
+                    modified: true,
+                },
+                Submodule {
+                    name: String::from("docs.v2"),
+                    path: String::from("my docs"),
+                    url: Some(String::from("https://example.com/docs.git")),
+                    branch: None,
+                    recorded_commit: Some(String::from("cccc")),
+                    checked_out_commit: None,
+                    initialised: false,
+                    modified: false,
+                },
+                Submodule {
+                    name: String::from("new"),
+                    path: String::from("new"),
+                    url: None,
+                    branch: None,
+                    recorded_commit: None,
+                    checked_out_commit: None,
+                    initialised: false,
+                    modified: false,
+                },
+            ],
+            submodules
+        );
+        assert!(parse_gitmodules("submodule.lib.path").is_err());
+        assert!(apply_status(&mut submodules, &gitlinks, "*eeee new\n").is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use super::GitError;
+use crate::log::{parse_signature, Signature};
+use crate::types::{Result, TagName};
+
+const FIELDS: &[&str] = &[
+    "%(refname:strip=2)",
+    "%(objecttype)",
+    "%(objectname)",
+    "%(*objectname)",
+    "%(taggername)",
+    "%(taggeremail)",
+    "%(taggerdate:raw)",
+    "%(contents:subject)",
+    "%(contents:body)",
+];
+
+///A tag as returned by `Repository::list_tags`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    ///The object the tag refers to, usually a commit
+    pub target: String,
+    ///The hash of the tag object, `None` for lightweight tags
+    pub object: Option<String>,
+    ///Who created the tag, `None` for lightweight tags
+    pub tagger: Option<Signature>,
+    ///The tag message without any signature, `None` for lightweight tags
+    pub message: Option<String>,
+}
+
+impl Tag {
+    ///Returns true if this is an annotated tag rather than a lightweight one
+    pub fn is_annotated(&self) -> bool {
+        self.object.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Signing {
+    Default,
+    Disabled,
+    Enabled(Option<String>),
+}
+
+///Options for creating a tag with `Repository::create_tag`
+///
+///By default a lightweight tag pointing at `HEAD` is created. Setting a message or signing
+///the tag creates an annotated tag instead.
+#[derive(Debug, Clone)]
+pub struct TagOptions {
+    message: Option<String>,
+    target: Option<String>,
+    force: bool,
+    signing: Signing,
+}
+
+impl Default for TagOptions {
+    fn default() -> TagOptions {
+        TagOptions {
+            message: None,
+            target: None,
+            force: false,
+            signing: Signing::Default,
+        }
+    }
+}
+
+impl TagOptions {
+    pub fn new() -> TagOptions {
+        TagOptions::default()
+    }
+
+    ///Create an annotated tag with the given message, which is passed to git on stdin
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_owned());
+        self
+    }
+
+    ///Tag the given revision rather than `HEAD`
+    pub fn target(mut self, revision: &str) -> Self {
+        self.target = Some(revision.to_owned());
+        self
+    }
+
+    ///Replace an existing tag with the same name
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    ///Create a signed tag, optionally with a specific key rather than the configured one
+    pub fn sign(mut self, key: Option<&str>) -> Self {
+        self.signing = Signing::Enabled(key.map(|k| k.to_owned()));
+        self
+    }
+
+    ///Don't sign the tag, even if `tag.gpgSign` is set
+    pub fn no_sign(mut self) -> Self {
+        self.signing = Signing::Disabled;
+        self
+    }
+
+    pub(crate) fn args(&self, name: &TagName) -> Vec<String> {
+        let mut args = vec![String::from("tag")];
+        if self.force {
+            args.push(String::from("--force"));
+        }
+        match &self.signing {
+            Signing::Default => {}
+            Signing::Disabled => args.push(String::from("--no-sign")),
+            Signing::Enabled(None) => args.push(String::from("--sign")),
+            Signing::Enabled(Some(key)) => args.push(format!("--local-user={}", key)),
+        }
+        if self.input().is_some() {
+            args.push(String::from("--annotate"));
+            args.push(String::from("--file=-"));
+        }
+        args.push(String::from("--"));
+        args.push(name.value.clone());
+        if let Some(target) = &self.target {
+            args.push(target.clone());
+        }
+        args
+    }
+
+    pub(crate) fn input(&self) -> Option<Vec<u8>> {
+        match (&self.message, &self.signing) {
+            (Some(message), _) => Some(message.as_bytes().to_vec()),
+            (None, Signing::Enabled(_)) => Some(Vec::new()),
+            (None, _) => None,
+        }
+    }
+}
+
+///How `Repository::list_tags` orders tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagSort {
+    Name,
+    ///Treat names as version numbers, so that `v1.10` sorts after `v1.9`
+    Version,
+    ///The tagger date of annotated tags, or the committer date of tagged commits
+    CreatorDate,
+}
+
+///Options controlling which tags are returned by `Repository::list_tags`
+#[derive(Debug, Clone)]
+pub struct ListTagsOptions {
+    patterns: Vec<String>,
+    sort: TagSort,
+    descending: bool,
+}
+
+impl Default for ListTagsOptions {
+    fn default() -> ListTagsOptions {
+        ListTagsOptions {
+            patterns: Vec::new(),
+            sort: TagSort::Name,
+            descending: false,
+        }
+    }
+}
+
+impl ListTagsOptions {
+    pub fn new() -> ListTagsOptions {
+        ListTagsOptions::default()
+    }
+
+    ///Only include tags matching the given glob, such as `v1.*`
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_owned());
+        self
+    }
+
+    pub fn sort(mut self, sort: TagSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    ///Reverse the sort order
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        let key = match self.sort {
+            TagSort::Name => "refname",
+            TagSort::Version => "version:refname",
+            TagSort::CreatorDate => "creatordate",
+        };
+        let mut args = vec![
+            String::from("tag"),
+            String::from("--list"),
+            format!("--format={}%00", FIELDS.join("%00")),
+            format!("--sort={}{}", if self.descending { "-" } else { "" }, key),
+            String::from("--"),
+        ];
+        args.extend(self.patterns.iter().cloned());
+        args
+    }
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+///Parse the output of `git tag --list` using the `FIELDS` format, where every record is
+///terminated by a NUL and a newline
+pub(crate) fn parse_tags(output: &str) -> Result<Vec<Tag>> {
+    output
+        .split_terminator("\0\n")
+        .map(|record| {
+            let fields: Vec<&str> = record.split('\0').collect();
+            if fields.len() != FIELDS.len() {
+                return Err(unexpected(record));
+            }
+
+            let annotated = fields[1] == "tag";
+            let message = match fields[8].trim_end() {
+                "" => fields[7].to_owned(),
+                body => format!("{}\n\n{}", fields[7], body),
+            };
+            Ok(Tag {
+                name: fields[0].to_owned(),
+                target: if annotated { fields[3] } else { fields[2] }.to_owned(),
+                object: Some(fields[2].to_owned()).filter(|_| annotated),
+                tagger: if annotated && !fields[6].is_empty() {
+                    let email = fields[5].trim_start_matches('<').trim_end_matches('>');
+                    Some(parse_signature(fields[4], email, fields[6])?)
+                } else {
+                    None
+                },
+                message: Some(message).filter(|_| annotated),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_create_args() {
+        let name = TagName::from_str("v1.0").unwrap();
+
+        assert_eq!(vec!["tag", "--", "v1.0"], TagOptions::new().args(&name));
+        assert_eq!(None, TagOptions::new().input());
+
+        let options = TagOptions::new()
+            .message("Release 1.0")
+            .target("main")
+            .force()
+            .sign(Some("ABCD"));
+        assert_eq!(
+            vec![
+                "tag",
+                "--force",
+                "--local-user=ABCD",
+                "--annotate",
+                "--file=-",
+                "--",
+                "v1.0",
+                "main"
+            ],
+            options.args(&name)
+        );
+        assert_eq!(Some(b"Release 1.0".to_vec()), options.input());
+    }
+
+    #[test]
+    fn test_list_args() {
+        let options = ListTagsOptions::new()
+            .pattern("v1.*")
+            .sort(TagSort::Version)
+            .descending();
+
+        let args = options.args();
+
+        assert_eq!("--sort=-version:refname", args[3]);
+        assert_eq!(vec!["--", "v1.*"], args[4..].to_vec());
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let output = concat!(
+            "v1\0commit\0aaaa\0\0\0\0\0Some commit\0\0\n",
+            "v2\0tag\0bbbb\0aaaa\0Jane Doe\0<jane@example.com>\01700000000 +0100\0",
+            "Release 2\0Notes\n\0\n",
+        );
+
+        let tags = parse_tags(output).unwrap();
+
+        assert_eq!(
+            vec![
+                Tag {
+                    name: String::from("v1"),
+                    target: String::from("aaaa"),
+                    object: None,
+                    tagger: None,
+                    message: None,
+                },
+                Tag {
+                    name: String::from("v2"),
+                    target: String::from("aaaa"),
+                    object: Some(String::from("bbbb")),
+                    tagger: Some(Signature {
+                        name: String::from("Jane Doe"),
+                        email: String::from("jane@example.com"),
+                        timestamp: 1700000000,
+                        utc_offset: 60,
+                    }),
+                    message: Some(String::from("Release 2\n\nNotes")),
+                },
+            ],
+            tags
+        );
+        assert!(parse_tags("").unwrap().is_empty());
+        assert!(parse_tags("v1\0commit\0\n").is_err());
+    }
+}
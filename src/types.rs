@@ -72,6 +72,41 @@ impl<'de> Deserialize<'de> for BranchName {
     }
 }
 
+#[derive(Debug)]
+pub struct TagName {
+    pub(crate) value: String
+}
+
+impl FromStr for TagName {
+    type Err = GitError;
+    fn from_str(s: &str) -> Result<Self> {
+        if is_valid_reference_name(s) {
+            Ok(TagName {
+                value: String::from(s)
+            })
+        } else {
+            Err(GitError::InvalidRefName)
+        }
+    }
+}
+
+impl Display for TagName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TagName {
+    fn deserialize<D>(deserializer: D) -> stdResult<TagName, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        TagName::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
 const INVALID_REFERENCE_CHARS: [char; 5] = [' ', '~', '^', ':', '\\'];
 const INVALID_REFERENCE_START: &str = "-";
 const INVALID_REFERENCE_END: &str = ".";
@@ -170,6 +205,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn test_invalid_reference_names() {
         let invalid_references = vec!(
             "double..dot",
@@ -0,0 +1,239 @@
+use super::GitError;
+use crate::types::{BranchName, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+pub(crate) const LIST_ARGS: [&str; 4] = ["worktree", "list", "--porcelain", "-z"];
+
+///Options for `Repository::add_worktree`
+///
+///By default git checks out a new branch named after the last component of the path.
+#[derive(Debug, Clone, Default)]
+pub struct WorktreeOptions {
+    commit: Option<String>,
+    new_branch: Option<String>,
+    detach: bool,
+}
+
+impl WorktreeOptions {
+    pub fn new() -> WorktreeOptions {
+        WorktreeOptions::default()
+    }
+
+    ///Check out the given branch or commit, or start the new branch from it when used with
+    ///`new_branch`
+    pub fn branch(mut self, commit: &str) -> Self {
+        self.commit = Some(commit.to_owned());
+        self
+    }
+
+    ///Create a branch with the given name and check it out
+    pub fn new_branch(mut self, name: &BranchName) -> Self {
+        self.new_branch = Some(name.value.clone());
+        self
+    }
+
+    ///Check out a detached `HEAD` rather than a branch
+    pub fn detach(mut self) -> Self {
+        self.detach = true;
+        self
+    }
+
+    pub(crate) fn args(&self, path: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec!["worktree".into(), "add".into()];
+        if self.detach {
+            args.push("--detach".into());
+        }
+        if let Some(name) = &self.new_branch {
+            args.push("-b".into());
+            args.push(name.into());
+        }
+        args.push("--".into());
+        args.push(path.into());
+        args.extend(self.commit.iter().map(|c| c.into()));
+        args
+    }
+}
+
+pub(crate) fn remove_args(path: &Path, force: bool) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["worktree".into(), "remove".into()];
+    if force {
+        args.push("--force".into());
+    }
+    args.push("--".into());
+    args.push(path.into());
+    args
+}
+
+pub(crate) fn lock_args(path: &Path, reason: Option<&str>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["worktree".into(), "lock".into()];
+    if let Some(reason) = reason {
+        args.push(format!("--reason={}", reason).into());
+    }
+    args.push("--".into());
+    args.push(path.into());
+    args
+}
+
+pub(crate) fn unlock_args(path: &Path) -> Vec<OsString> {
+    vec!["worktree".into(), "unlock".into(), "--".into(), path.into()]
+}
+
+///A working tree as returned by `Repository::list_worktrees`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    pub path: PathBuf,
+    ///The commit checked out, `None` for a bare repository or a branch with no commits yet
+    pub head: Option<String>,
+    ///The short name of the branch checked out, `None` if `HEAD` is detached
+    pub branch: Option<String>,
+    pub is_bare: bool,
+    pub is_detached: bool,
+    ///Whether the worktree is locked against being pruned, moved or removed
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    ///Whether the worktree is missing and would be removed by `Repository::prune_worktrees`
+    pub prunable: bool,
+    pub prune_reason: Option<String>,
+}
+
+fn unexpected(record: &str) -> GitError {
+    GitError::UnexpectedOutput(record.to_owned())
+}
+
+fn reason(value: Option<&str>) -> Option<String> {
+    value.filter(|r| !r.is_empty()).map(|r| r.to_owned())
+}
+
+///Parse the output of `git worktree list --porcelain -z`, where each attribute is terminated
+///by a NUL and each worktree by an extra NUL
+pub(crate) fn parse_worktrees(output: &str) -> Result<Vec<Worktree>> {
+    let mut worktrees = Vec::new();
+    let mut current: Option<Worktree> = None;
+    for attribute in output.split_terminator('\0') {
+        if attribute.is_empty() {
+            worktrees.extend(current.take());
+            continue;
+        }
+
+        let (key, value) = match attribute.split_once(' ') {
+            Some((key, value)) => (key, Some(value)),
+            None => (attribute, None),
+        };
+        if key == "worktree" {
+            current = Some(Worktree {
+                path: PathBuf::from(value.ok_or_else(|| unexpected(attribute))?),
+                head: None,
+                branch: None,
+                is_bare: false,
+                is_detached: false,
+                locked: false,
+                lock_reason: None,
+                prunable: false,
+                prune_reason: None,
+            });
+            continue;
+        }
+
+        let worktree = current.as_mut().ok_or_else(|| unexpected(attribute))?;
+        match (key, value) {
+            ("HEAD", Some(head)) => worktree.head = Some(head.to_owned()),
+            ("branch", Some(branch)) => {
+                worktree.branch = Some(
+                    branch
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(branch)
+                        .to_owned(),
+                )
+            }
+            ("bare", None) => worktree.is_bare = true,
+            ("detached", None) => worktree.is_detached = true,
+            ("locked", reason_text) => {
+                worktree.locked = true;
+                worktree.lock_reason = reason(reason_text);
+            }
+            ("prunable", reason_text) => {
+                worktree.prunable = true;
+                worktree.prune_reason = reason(reason_text);
+            }
+            //Ignore attributes added by later versions of git
+            _ => {}
+        }
+    }
+    worktrees.extend(current);
+    Ok(worktrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_add_args() {
+        let path = Path::new("../ci-1");
+
+        assert_eq!(
+            vec!["worktree", "add", "--", "../ci-1"],
+            WorktreeOptions::new().args(path)
+        );
+        assert_eq!(
+            vec!["worktree", "add", "--detach", "--", "../ci-1", "v1.0"],
+            WorktreeOptions::new().branch("v1.0").detach().args(path)
+        );
+        assert_eq!(
+            vec!["worktree", "add", "-b", "ci", "--", "../ci-1", "main"],
+            WorktreeOptions::new()
+                .branch("main")
+                .new_branch(&BranchName::from_str("ci").unwrap())
+                .args(path)
+        );
+        assert_eq!(
+            vec!["worktree", "remove", "--force", "--", "../ci-1"],
+            remove_args(path, true)
+        );
+        assert_eq!(
+            vec!["worktree", "lock", "--reason=ci job", "--", "../ci-1"],
+            lock_args(path, Some("ci job"))
+        );
+    }
+
+    #[test]
+    fn test_parse_worktrees() {
+        let output = concat!(
+            "worktree /srv/repo\0bare\0\0",
+            "worktree /srv/main\0HEAD aaaa\0branch refs/heads/main\0\0",
+            "worktree /srv/ci\0HEAD bbbb\0detached\0locked ci job\0\0",
+            "worktree /srv/gone\0HEAD cccc\0branch refs/heads/old\0locked\0",
+            "prunable gitdir file points to non-existent location\0\0",
+        );
+
+        let worktrees = parse_worktrees(output).unwrap();
+
+        assert_eq!(4, worktrees.len());
+        assert!(worktrees[0].is_bare);
+        assert_eq!(None, worktrees[0].head);
+        assert_eq!(
+            Worktree {
+                path: PathBuf::from("/srv/main"),
+                head: Some(String::from("aaaa")),
+                branch: Some(String::from("main")),
+                is_bare: false,
+                is_detached: false,
+                locked: false,
+                lock_reason: None,
+                prunable: false,
+                prune_reason: None,
+            },
+            worktrees[1]
+        );
+        assert!(worktrees[2].is_detached);
+        assert_eq!(None, worktrees[2].branch);
+        assert_eq!(Some(String::from("ci job")), worktrees[2].lock_reason);
+        assert!(worktrees[3].locked);
+        assert_eq!(None, worktrees[3].lock_reason);
+        assert!(worktrees[3].prunable);
+        assert!(parse_worktrees("").unwrap().is_empty());
+        assert!(parse_worktrees("HEAD aaaa\0\0").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "tokio")]
+
+use rustygit::commit::CommitBuilder;
+use rustygit::error::GitError;
+use rustygit::log::LogOptions;
+use rustygit::progress::{Progress, Stage};
+use rustygit::rebase::{RebaseOptions, RebaseOutcome};
+use rustygit::{AsyncRepository, Repository};
+use std::fs;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_async_commit_and_status() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = AsyncRepository::init(&dir).await.unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    assert_eq!(vec!["somefile"], repo.list_untracked().await.unwrap());
+
+    repo.add(vec!["somefile"]).await.unwrap();
+    assert_eq!(vec!["somefile"], repo.list_added().await.unwrap());
+
+    let hash = repo.commit(&CommitBuilder::new("Commit 1")).await.unwrap();
+    assert_eq!(hash, repo.get_hash(false).await.unwrap());
+    assert!(repo.status().await.unwrap().is_clean());
+
+    let commits = repo.log(&LogOptions::new()).await.unwrap();
+    assert_eq!(1, commits.len());
+    assert_eq!("Commit 1", commits[0].subject);
+
+    let val = repo.cmd_out(["ls-files"]).await.unwrap();
+    assert_eq!(vec!["somefile"], val);
+}
+
+#[tokio::test]
+async fn test_async_from_repository() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo: AsyncRepository = Repository::init(&dir).unwrap().into();
+
+    assert!(repo.get_hash(false).await.is_err());
+    assert!(repo.list_branches().await.unwrap().is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_async_timeout() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = AsyncRepository::init(&dir).await.unwrap();
+
+    let hook = dir.as_ref().join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\necho checking >&2\nsleep 30\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let result = repo
+        .with_timeout(Duration::from_millis(500))
+        .commit(&CommitBuilder::new("Commit 1").allow_empty())
+        .await;
+
+    match result {
+        Err(GitError::Timeout { stderr, .. }) => assert_eq!("checking\n", stderr),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_async_fetch_with_progress() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    let upstream = Repository::init(&upstream_dir).unwrap();
+    fs::write(upstream_dir.as_ref().join("somefile"), "Some content").unwrap();
+    upstream.add(vec!["somefile"]).unwrap();
+    upstream.commit_all("Commit 1").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = AsyncRepository::init(&dir).await.unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .await
+    .unwrap();
+
+    let mut updates: Vec<Progress> = Vec::new();
+    repo.fetch_remote_with_progress("origin", |p| updates.push(p))
+        .await
+        .unwrap();
+
+    assert!(updates.iter().any(|p| p.stage == Stage::Counting && p.done));
+}
+
+#[tokio::test]
+async fn test_async_rebase() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = AsyncRepository::init(&dir).await.unwrap();
+    fs::write(dir.as_ref().join("file"), "base").unwrap();
+    repo.add(vec!["file"]).await.unwrap();
+    repo.commit(&CommitBuilder::new("Base")).await.unwrap();
+    fs::write(dir.as_ref().join("file"), "changed").unwrap();
+    repo.add(vec!["file"]).await.unwrap();
+    repo.commit(&CommitBuilder::new("Change")).await.unwrap();
+    let branch = repo.current_branch().await.unwrap();
+
+    match repo
+        .rebase(&RebaseOptions::new().upstream("HEAD~1").exec("false"))
+        .await
+        .unwrap()
+    {
+        RebaseOutcome::Stopped { state, conflicts } => {
+            assert_eq!((2, 2), (state.current, state.total));
+            assert_eq!(branch, state.branch);
+            assert!(conflicts.is_empty());
+        }
+        other => panic!("expected the rebase to stop, got {:?}", other),
+    }
+    assert!(repo.rebase_state().await.unwrap().is_some());
+    repo.rebase_abort().await.unwrap();
+    assert_eq!(None, repo.rebase_state().await.unwrap());
+}
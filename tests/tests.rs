@@ -1,13 +1,42 @@
+use rustygit::branch::BranchKind;
+use rustygit::commit::CommitBuilder;
+use rustygit::config::ConfigScope;
+use rustygit::diff::{DiffSpec, FileDiffStatus, LineKind};
+use rustygit::fetch::{FetchOptions, UpdateFlag};
+use rustygit::log::LogOptions;
+use rustygit::merge::{FastForward, MergeOptions, MergeOutcome};
+use rustygit::metadata::ObjectFormat;
+use rustygit::progress::{Progress, Stage};
+use rustygit::pull::{PullMode, PullOptions, PullOutcome};
+use rustygit::push::{PushOptions, PushStatus};
+use rustygit::rebase::{RebaseOptions, RebaseOutcome};
+use rustygit::remote::RemoteHead;
+use rustygit::reset::ResetMode;
+use rustygit::runner::{
+    CancellationToken, GitOutput, Interruption, RecordingRunner, ScriptedRunner,
+};
+use rustygit::stash::{StashApplyOutcome, StashOptions};
+use rustygit::status::FileState;
+use rustygit::submodule::SubmoduleUpdateOptions;
+use rustygit::tag::{ListTagsOptions, TagOptions, TagSort};
 use rustygit::types::GitUrl;
-use rustygit::{Repository, types::BranchName, error::GitError};
+use rustygit::types::TagName;
+use rustygit::worktree::WorktreeOptions;
+use rustygit::{error::GitError, types::BranchName, Repository};
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::process::Command;
 use std::str::{self, FromStr};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+#[allow(clippy::single_component_path_imports)]
 use tempfile;
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_init() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -24,6 +53,7 @@ fn test_init() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_add_single() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -43,6 +73,7 @@ fn test_add_single() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_add_multiple() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -66,6 +97,7 @@ fn test_add_multiple() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_commit_all() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -88,6 +120,52 @@ fn test_commit_all() {
         .contains("some commit message"));
 }
 
+#[test]
+fn test_commit_builder() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    let first = repo
+        .commit(
+            &CommitBuilder::new("-first line\n\nsecond paragraph")
+                .author("Jane Doe", "jane@example.com")
+                .author_date("2020-01-01T12:00:00Z")
+                .committer("John Doe", "john@example.com")
+                .committer_date("2020-01-02T12:00:00Z"),
+        )
+        .unwrap();
+    assert_eq!(repo.get_hash(false).unwrap(), first);
+
+    let commits = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!("-first line", commits[0].subject);
+    assert_eq!("second paragraph", commits[0].body);
+    assert_eq!("jane@example.com", commits[0].author.email);
+    assert_eq!(1577880000, commits[0].author.timestamp);
+    assert_eq!("John Doe", commits[0].committer.name);
+    assert_eq!(1577966400, commits[0].committer.timestamp);
+
+    // only the index is committed unless all() is requested
+    fs::write(dir.as_ref().join("somefile"), "Some changed content").unwrap();
+    assert!(repo.commit(&CommitBuilder::new("nothing staged")).is_err());
+
+    let empty = repo
+        .commit(&CommitBuilder::new("empty").allow_empty())
+        .unwrap();
+    assert_ne!(first, empty);
+
+    let amended = repo
+        .commit(&CommitBuilder::new("amended").amend().all())
+        .unwrap();
+    let commits = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!(2, commits.len());
+    assert_eq!(amended, commits[0].hash);
+    assert_eq!("amended", commits[0].subject);
+    assert_eq!(0, repo.list_modified().unwrap().len());
+}
+
 #[test]
 fn test_remove_uncommitted_single() {
     let dir = tempfile::tempdir().unwrap();
@@ -103,6 +181,7 @@ fn test_remove_uncommitted_single() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_remove_uncommitted_single_force() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -123,6 +202,7 @@ fn test_remove_uncommitted_single_force() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_remove_committed_single() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -144,6 +224,7 @@ fn test_remove_committed_single() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_remove_commited_multiple() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -185,6 +266,7 @@ fn test_remove_uncommited_multiple() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_remove_uncommited_multiple_force() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -241,6 +323,7 @@ fn test_list_untracked() {
 }
 
 #[test]
+#[allow(clippy::unused_io_amount)]
 fn test_list_modified() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -260,6 +343,70 @@ fn test_list_modified() {
     assert!(output.contains(&String::from("anotherfile")));
 }
 
+#[test]
+fn test_list_modified_staged() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit_all("some msg").unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some changed content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+
+    let output = repo.list_modified().unwrap();
+
+    assert_eq!(vec!["somefile"], output);
+}
+
+#[test]
+fn test_status() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    fs::write(dir.as_ref().join("anotherfile"), "Other content").unwrap();
+    fs::write(dir.as_ref().join(".gitignore"), "ignored\n").unwrap();
+    repo.add(vec!["somefile", "anotherfile", ".gitignore"])
+        .unwrap();
+    repo.commit_all("some msg").unwrap();
+
+    repo.cmd(["mv", "somefile", "renamed file"]).unwrap();
+    fs::remove_file(dir.as_ref().join("anotherfile")).unwrap();
+    fs::write(dir.as_ref().join("untracked"), "").unwrap();
+    fs::write(dir.as_ref().join("ignored"), "").unwrap();
+
+    assert!(repo.status().unwrap().ignored.is_empty());
+    let status = repo.status_with_ignored().unwrap();
+
+    assert!(status.branch.head.is_some());
+    assert_eq!(Some(repo.get_hash(false).unwrap()), status.branch.oid);
+    assert_eq!(2, status.entries.len());
+
+    let renamed = status
+        .entries
+        .iter()
+        .find(|e| e.path == "renamed file")
+        .unwrap();
+    assert_eq!(FileState::Renamed, renamed.index);
+    assert_eq!(Some(String::from("somefile")), renamed.original_path);
+
+    let deleted = status
+        .entries
+        .iter()
+        .find(|e| e.path == "anotherfile")
+        .unwrap();
+    assert_eq!(FileState::Unmodified, deleted.index);
+    assert_eq!(FileState::Deleted, deleted.worktree);
+
+    assert_eq!(vec!["untracked"], status.untracked);
+    assert_eq!(vec!["ignored"], status.ignored);
+    assert!(!status.is_clean());
+}
+
 #[test]
 fn test_list_tracked() {
     let dir = tempfile::tempdir().unwrap();
@@ -296,6 +443,7 @@ fn test_list_tracked() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_get_hash() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -321,6 +469,129 @@ fn test_get_hash() {
 }
 
 #[test]
+fn test_log() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit_all("Commit 1").unwrap();
+    let first = repo.get_hash(false).unwrap();
+
+    fs::write(dir.as_ref().join("anotherfile"), "Some content").unwrap();
+    repo.add(vec!["anotherfile"]).unwrap();
+    repo.commit_all("Commit 2\n\nMore details\n\nReviewed-by: Someone <someone@example.com>")
+        .unwrap();
+    let second = repo.get_hash(false).unwrap();
+
+    let commits = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!(2, commits.len());
+    assert_eq!(second, commits[0].hash);
+    assert_eq!(vec![first.clone()], commits[0].parents);
+    assert_eq!("Commit 2", commits[0].subject);
+    assert_eq!(1, commits[0].trailers.len());
+    assert_eq!("Reviewed-by", commits[0].trailers[0].key);
+    assert_eq!(first, commits[1].hash);
+    assert!(commits[1].parents.is_empty());
+
+    let commits = repo.log(&LogOptions::new().max_count(1).skip(1)).unwrap();
+    assert_eq!(
+        vec![first.clone()],
+        commits.into_iter().map(|c| c.hash).collect::<Vec<_>>()
+    );
+
+    let commits = repo.log(&LogOptions::new().path("anotherfile")).unwrap();
+    assert_eq!(1, commits.len());
+    assert_eq!(second, commits[0].hash);
+
+    let range = format!("{}..HEAD", first);
+    let commits = repo.log(&LogOptions::new().revision(&range)).unwrap();
+    assert_eq!(1, commits.len());
+
+    assert!(repo
+        .log(&LogOptions::new().merges_only())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_diff() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "one\ntwo\nthree\n").unwrap();
+    fs::write(
+        dir.as_ref().join("anotherfile"),
+        "some longer content to keep\n",
+    )
+    .unwrap();
+    repo.add(vec!["somefile", "anotherfile"]).unwrap();
+    repo.commit_all("Commit 1").unwrap();
+    let first = repo.get_hash(false).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "one\n2\nthree\n").unwrap();
+
+    let diff = repo.diff(&DiffSpec::worktree_to_index()).unwrap();
+    assert_eq!(1, diff.files.len());
+    let file = &diff.files[0];
+    assert_eq!(Some(String::from("somefile")), file.new_path);
+    assert_eq!(FileDiffStatus::Modified, file.status);
+    assert_eq!(1, file.hunks.len());
+    let changed: Vec<_> = file.hunks[0]
+        .lines
+        .iter()
+        .filter(|line| line.kind != LineKind::Context)
+        .map(|line| (line.kind, line.content.as_str()))
+        .collect();
+    assert_eq!(
+        vec![(LineKind::Deletion, "two"), (LineKind::Addition, "2")],
+        changed
+    );
+
+    assert!(repo
+        .diff(&DiffSpec::index_to_head())
+        .unwrap()
+        .files
+        .is_empty());
+
+    repo.add(vec!["somefile"]).unwrap();
+    repo.cmd(["mv", "anotherfile", "renamedfile"]).unwrap();
+
+    let diff = repo.diff(&DiffSpec::index_to_head()).unwrap();
+    assert_eq!(2, diff.files.len());
+    let renamed = diff
+        .files
+        .iter()
+        .find(|f| f.status == FileDiffStatus::Renamed(100))
+        .unwrap();
+    assert_eq!(Some(String::from("anotherfile")), renamed.old_path);
+    assert_eq!(Some(String::from("renamedfile")), renamed.new_path);
+
+    repo.commit_all("Commit 2").unwrap();
+
+    let diff = repo
+        .diff(
+            &DiffSpec::commits(&first, "HEAD")
+                .no_renames()
+                .path("anotherfile"),
+        )
+        .unwrap();
+    assert_eq!(1, diff.files.len());
+    assert_eq!(FileDiffStatus::Deleted, diff.files[0].status);
+
+    let stat = repo.diff_stat(&DiffSpec::commits(&first, "HEAD")).unwrap();
+    assert_eq!(2, stat.files.len());
+    assert_eq!(1, stat.insertions());
+    assert_eq!(1, stat.deletions());
+}
+
+#[test]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::needless_borrows_for_generic_args
+)]
 fn test_get_error() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -333,11 +604,21 @@ fn test_get_error() {
     let result = repo.switch_branch(&BranchName::from_str("no_branch").unwrap());
     if let Err(e) = result {
         match e {
-            GitError::GitError { stdout, stderr } => {
-                assert!(stdout.is_empty());
-                assert_eq!(stderr, "error: pathspec 'no_branch' did not match any file(s) known to git\n");
+            GitError::PathspecNotMatched {
+                pathspecs,
+                stderr,
+                command,
+                exit_code,
+            } => {
+                assert_eq!(vec!["no_branch"], pathspecs);
+                assert_eq!(
+                    stderr,
+                    "error: pathspec 'no_branch' did not match any file(s) known to git\n"
+                );
+                assert_eq!("git checkout no_branch", command);
+                assert_eq!(Some(1), exit_code);
             }
-            _ => assert!(false, "Expected GitError, got {:?}", e),
+            _ => assert!(false, "Expected PathspecNotMatched, got {:?}", e),
         }
     } else {
         assert!(false, "Expected failing checkout of a unknown branch");
@@ -345,6 +626,55 @@ fn test_get_error() {
 }
 
 #[test]
+fn test_error_classification() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let result = Repository::new(&dir).list_branches();
+    assert!(matches!(result, Err(GitError::NotARepository { .. })));
+
+    let repo = Repository::init(&dir).unwrap();
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit_all("Commit 1").unwrap();
+
+    let result = repo.commit(&CommitBuilder::new("Commit 2"));
+    assert!(matches!(result, Err(GitError::NothingToCommit { .. })));
+
+    let result = repo.log(&LogOptions::new().revision("no_branch"));
+    match result {
+        Err(GitError::RefNotFound { reference, .. }) => assert_eq!("no_branch", reference),
+        other => panic!("Expected RefNotFound, got {:?}", other),
+    }
+
+    repo.create_local_branch(&BranchName::from_str("other").unwrap())
+        .unwrap();
+    fs::write(dir.as_ref().join("somefile"), "Other content").unwrap();
+    repo.commit_all("Commit 2").unwrap();
+    repo.cmd(["checkout", "-"]).unwrap();
+    fs::write(dir.as_ref().join("somefile"), "Conflicting content").unwrap();
+
+    let result = repo.switch_branch(&BranchName::from_str("other").unwrap());
+    match result {
+        Err(GitError::DirtyWorktree { paths, .. }) => assert_eq!(vec!["somefile"], paths),
+        other => panic!("Expected DirtyWorktree, got {:?}", other),
+    }
+
+    repo.commit_all("Commit 3").unwrap();
+    let result = repo.cmd(["merge", "other"]);
+    match result {
+        Err(GitError::MergeConflict {
+            paths, exit_code, ..
+        }) => {
+            assert_eq!(vec!["somefile"], paths);
+            assert_eq!(Some(1), exit_code);
+        }
+        other => panic!("Expected MergeConflict, got {:?}", other),
+    }
+}
+
+#[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_cmd() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -384,6 +714,7 @@ fn test_cmd() {
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_cmd_out() {
     let dir = tempfile::tempdir().unwrap();
 
@@ -417,8 +748,14 @@ fn test_show_remotes() {
     let dir = tempfile::tempdir().unwrap();
 
     let repo = Repository::init(&dir).unwrap();
-    let _ = repo.add_remote("origin", &GitUrl::from_str("git@github.com:random/repo.git").unwrap());
-    let _ = repo.add_remote("copy", &GitUrl::from_str("git@github.com:another_random/repo.git").unwrap());
+    let _ = repo.add_remote(
+        "origin",
+        &GitUrl::from_str("git@github.com:random/repo.git").unwrap(),
+    );
+    let _ = repo.add_remote(
+        "copy",
+        &GitUrl::from_str("git@github.com:another_random/repo.git").unwrap(),
+    );
     let remotes = repo.list_remotes().unwrap();
     assert_eq!(2, remotes.len());
     assert_eq!(vec!["copy", "origin"], remotes);
@@ -429,16 +766,1383 @@ fn test_show_remote_uri() {
     let dir = tempfile::tempdir().unwrap();
 
     let repo = Repository::init(&dir).unwrap();
-    let _ = repo.add_remote("origin", &GitUrl::from_str("git@github.com:random/repo.git").unwrap());
+    let _ = repo.add_remote(
+        "origin",
+        &GitUrl::from_str("git@github.com:random/repo.git").unwrap(),
+    );
     let remote_uri = repo.show_remote_uri("origin").unwrap();
     assert_eq!("git@github.com:random/repo.git", remote_uri);
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_no_remote_uri() {
     let dir = tempfile::tempdir().unwrap();
 
     let repo = Repository::init(&dir).unwrap();
     let remote_uri = repo.list_remotes();
     assert_eq!(true, remote_uri.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_scripted_runner() {
+    let runner = Arc::new(ScriptedRunner::new());
+    runner.push_success("main\nfeature\n").push_failure(
+        128,
+        "fatal: not a git repository (or any of the parent directories): .git\n",
+    );
+
+    let repo = Repository::with_runner("/some/repo", runner.clone());
+
+    assert_eq!(vec!["main", "feature"], repo.list_branches().unwrap());
+    assert!(matches!(
+        repo.get_hash(false),
+        Err(GitError::NotARepository { .. })
+    ));
+
+    let invocations = runner.invocations();
+    assert_eq!(2, invocations.len());
+    assert_eq!(std::path::Path::new("/some/repo"), invocations[0].cwd);
+    assert_eq!(
+        vec!["branch", "--format=%(refname:short)"],
+        invocations[0].args_lossy()
+    );
+    assert_eq!(vec!["rev-parse", "HEAD"], invocations[1].args_lossy());
+}
+
+#[test]
+fn test_recording_runner() {
+    let dir = tempfile::tempdir().unwrap();
+    Repository::init(&dir).unwrap();
+
+    let runner = Arc::new(RecordingRunner::new());
+    let repo = Repository::with_runner(&dir, runner.clone());
+
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit(&CommitBuilder::new("Commit 1")).unwrap();
+
+    let invocations = runner.invocations();
+    assert_eq!(3, invocations.len());
+    assert_eq!(vec!["add", "somefile"], invocations[0].args_lossy());
+    assert_eq!(
+        vec!["commit", "--quiet", "--file=-"],
+        invocations[1].args_lossy()
+    );
+    assert_eq!(Some(b"Commit 1".to_vec()), invocations[1].input);
+    assert_eq!(vec!["rev-parse", "HEAD"], invocations[2].args_lossy());
+}
+
+#[test]
+fn test_repository_builder() {
+    let dir = tempfile::tempdir().unwrap();
+    let work_tree = tempfile::tempdir().unwrap();
+
+    let runner = Arc::new(RecordingRunner::new());
+    let repo = Repository::builder(&dir)
+        .git_dir(dir.as_ref().join("repo.git"))
+        .work_tree(&work_tree)
+        .config("user.name", "Configured User")
+        .env("GIT_AUTHOR_EMAIL", "configured@example.com")
+        .env_remove("GIT_INDEX_FILE")
+        .runner(runner.clone())
+        .init()
+        .unwrap();
+
+    assert!(dir.as_ref().join("repo.git").join("HEAD").exists());
+    assert!(!dir.as_ref().join(".git").exists());
+
+    fs::write(work_tree.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit(&CommitBuilder::new("Commit 1")).unwrap();
+
+    let commits = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!("Configured User", commits[0].author.name);
+    assert_eq!("configured@example.com", commits[0].author.email);
+
+    let invocations = runner.invocations();
+    assert_eq!("init", invocations[0].args_lossy()[4]);
+    assert_eq!("-c", invocations[1].args_lossy()[0]);
+    assert_eq!(
+        vec![std::ffi::OsString::from("GIT_INDEX_FILE")],
+        invocations[1].env_remove
+    );
+
+    let missing = Repository::builder(&dir)
+        .git_binary("/nonexistent/git")
+        .build();
+    match missing.list_tracked() {
+        Err(GitError::Execution { source }) => {
+            assert_eq!(std::io::ErrorKind::NotFound, source.kind())
+        }
+        other => panic!("expected an execution error, got {:?}", other),
+    }
+}
+
+#[cfg(unix)]
+fn install_slow_hook(dir: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let hook = dir.join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\necho checking >&2\nsleep 30\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    install_slow_hook(dir.as_ref());
+
+    let started = Instant::now();
+    let result = repo
+        .with_timeout(Duration::from_millis(500))
+        .commit(&CommitBuilder::new("Commit 1").allow_empty());
+
+    assert!(started.elapsed() < Duration::from_secs(10));
+    match result {
+        Err(GitError::Timeout {
+            timeout,
+            stderr,
+            command,
+            ..
+        }) => {
+            assert_eq!(Duration::from_millis(500), timeout);
+            assert_eq!("checking\n", stderr);
+            assert_eq!("git commit --quiet --allow-empty --file=-", command);
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert!(repo.get_hash(false).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cancellation() {
+    let dir = tempfile::tempdir().unwrap();
+    let token = CancellationToken::new();
+    let repo = Repository::builder(&dir)
+        .cancellation(&token)
+        .init()
+        .unwrap();
+    install_slow_hook(dir.as_ref());
+
+    let canceller = token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        canceller.cancel();
+    });
+
+    let started = Instant::now();
+    let result = repo.commit(&CommitBuilder::new("Commit 1").allow_empty());
+
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(matches!(result, Err(GitError::Cancelled { .. })));
+    assert!(matches!(
+        repo.list_branches(),
+        Err(GitError::Cancelled { .. })
+    ));
+}
+
+#[test]
+fn test_scripted_timeout() {
+    let runner = Arc::new(ScriptedRunner::new());
+    runner.push(GitOutput {
+        stdout: b"partial \xe2\x82".to_vec(),
+        interrupted: Some(Interruption::TimedOut),
+        ..GitOutput::default()
+    });
+
+    let repo =
+        Repository::with_runner("/some/repo", runner.clone()).with_timeout(Duration::from_secs(1));
+
+    match repo.fetch_remote("origin") {
+        Err(GitError::Timeout {
+            timeout, stdout, ..
+        }) => {
+            assert_eq!(Duration::from_secs(1), timeout);
+            assert_eq!("partial \u{FFFD}", stdout);
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert_eq!(
+        Some(Duration::from_secs(1)),
+        runner.invocations()[0].timeout
+    );
+}
+
+#[test]
+fn test_fetch_and_push_with_progress() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    let upstream = Repository::init(&upstream_dir).unwrap();
+    for i in 1..=3 {
+        fs::write(
+            upstream_dir.as_ref().join(format!("file{}", i)),
+            "Some content",
+        )
+        .unwrap();
+        upstream.add(vec!["."]).unwrap();
+        upstream.commit_all(&format!("Commit {}", i)).unwrap();
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+
+    let mut updates: Vec<Progress> = Vec::new();
+    repo.fetch_remote_with_progress("origin", |p| updates.push(p))
+        .unwrap();
+
+    assert!(updates.iter().all(|p| p.remote));
+    let counting: Vec<&Progress> = updates
+        .iter()
+        .filter(|p| p.stage == Stage::Counting)
+        .collect();
+    assert!(counting.len() > 1);
+    assert!(counting.last().unwrap().done);
+    assert_eq!(Some(100), counting.last().unwrap().percent);
+    assert_eq!(
+        Some(counting.last().unwrap().current),
+        counting.last().unwrap().total
+    );
+
+    let bare_dir = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .current_dir(&bare_dir)
+        .args(["init", "--bare"])
+        .output()
+        .unwrap();
+    upstream
+        .cmd([
+            "remote",
+            "add",
+            "backup",
+            bare_dir.as_ref().to_str().unwrap(),
+        ])
+        .unwrap();
+    upstream.cmd(["config", "push.default", "current"]).unwrap();
+    upstream
+        .cmd(["config", "remote.pushDefault", "backup"])
+        .unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    upstream.push_with_progress(sender).unwrap();
+
+    let writing: Vec<Progress> = receiver
+        .iter()
+        .filter(|p| p.stage == Stage::Writing)
+        .collect();
+    assert!(writing.last().unwrap().done);
+    assert!(writing.last().unwrap().bytes.is_some());
+}
+
+#[test]
+fn test_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    fs::write(dir.as_ref().join("somefile"), "Some content").unwrap();
+    repo.add(vec!["somefile"]).unwrap();
+    repo.commit_all("Commit 1").unwrap();
+    let hash = repo.get_hash(false).unwrap();
+
+    let v1_9 = TagName::from_str("v1.9").unwrap();
+    let v1_10 = TagName::from_str("v1.10").unwrap();
+    repo.create_tag(&v1_9, &TagOptions::new()).unwrap();
+    repo.create_tag(&v1_10, &TagOptions::new().message("Release 1.10\n\nNotes"))
+        .unwrap();
+    repo.create_tag(&TagName::from_str("other").unwrap(), &TagOptions::new())
+        .unwrap();
+    assert!(repo.create_tag(&v1_9, &TagOptions::new()).is_err());
+
+    let tags = repo
+        .list_tags(
+            &ListTagsOptions::new()
+                .pattern("v1.*")
+                .sort(TagSort::Version),
+        )
+        .unwrap();
+    assert_eq!(
+        vec!["v1.9", "v1.10"],
+        tags.iter().map(|t| t.name.as_str()).collect::<Vec<_>>()
+    );
+    assert!(!tags[0].is_annotated());
+    assert_eq!(hash, tags[0].target);
+    assert!(tags[1].is_annotated());
+    assert_eq!(hash, tags[1].target);
+    assert_ne!(Some(hash), tags[1].object);
+    assert_eq!("Test User", tags[1].tagger.as_ref().unwrap().name);
+    assert_eq!(Some(String::from("Release 1.10\n\nNotes")), tags[1].message);
+
+    assert!(repo.verify_tag(&v1_10).is_err());
+
+    repo.delete_tag(&v1_9).unwrap();
+    assert_eq!(2, repo.list_tags(&ListTagsOptions::new()).unwrap().len());
+
+    let bare_dir = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .current_dir(&bare_dir)
+        .args(["init", "--bare"])
+        .output()
+        .unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        bare_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+    repo.push_tags("origin").unwrap();
+    let remote = Repository::new(&bare_dir);
+    assert_eq!(2, remote.list_tags(&ListTagsOptions::new()).unwrap().len());
+}
+
+#[test]
+fn test_branch_management() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    let upstream = Repository::init(&upstream_dir).unwrap();
+    fs::write(upstream_dir.as_ref().join("somefile"), "Some content").unwrap();
+    upstream.add(vec!["somefile"]).unwrap();
+    upstream.commit_all("Commit 1").unwrap();
+    let default_branch = upstream.current_branch().unwrap().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+    repo.fetch_remote("origin").unwrap();
+
+    let feature = BranchName::from_str("feature").unwrap();
+    repo.create_branch_from_startpoint(&feature, &format!("origin/{}", default_branch))
+        .unwrap();
+    fs::write(dir.as_ref().join("another"), "Some content").unwrap();
+    repo.add(vec!["another"]).unwrap();
+    repo.commit_all("Commit 2").unwrap();
+
+    assert_eq!(
+        Some(String::from("feature")),
+        repo.current_branch().unwrap()
+    );
+    repo.set_upstream(&feature, &format!("origin/{}", default_branch))
+        .unwrap();
+
+    let branches = repo.list_branches_detailed().unwrap();
+    assert_eq!(2, branches.len());
+    let local = &branches[0];
+    assert_eq!(
+        ("feature", BranchKind::Local, true),
+        (local.name.as_str(), local.kind, local.is_head)
+    );
+    assert_eq!(Some(format!("origin/{}", default_branch)), local.upstream);
+    assert_eq!((Some(1), Some(0)), (local.ahead, local.behind));
+    assert_eq!(repo.get_hash(false).unwrap(), local.commit);
+    let remote = &branches[1];
+    assert_eq!((BranchKind::Remote, false), (remote.kind, remote.is_head));
+    assert_eq!(None, remote.upstream);
+
+    repo.unset_upstream(&feature).unwrap();
+    assert_eq!(None, repo.list_branches_detailed().unwrap()[0].upstream);
+
+    let renamed = BranchName::from_str("renamed").unwrap();
+    repo.rename_branch(&feature, &renamed).unwrap();
+    assert_eq!(
+        Some(String::from("renamed")),
+        repo.current_branch().unwrap()
+    );
+
+    repo.cmd([
+        "checkout",
+        "--detach",
+        &format!("origin/{}", default_branch),
+    ])
+    .unwrap();
+    assert_eq!(None, repo.current_branch().unwrap());
+
+    assert!(matches!(
+        repo.delete_branch(&renamed, false),
+        Err(GitError::BranchNotMerged { .. })
+    ));
+    repo.delete_branch(&renamed, true).unwrap();
+    let branches = repo.list_branches_detailed().unwrap();
+    assert!(branches
+        .iter()
+        .all(|b| b.kind == BranchKind::Remote && !b.is_head));
+}
+
+fn commit_file(repo: &Repository, dir: &std::path::Path, name: &str, content: &str) -> String {
+    fs::write(dir.join(name), content).unwrap();
+    repo.add(vec![name]).unwrap();
+    repo.commit(&CommitBuilder::new(&format!("Write {}", name)))
+        .unwrap()
+}
+
+#[test]
+fn test_merge() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    commit_file(&repo, dir.as_ref(), "base", "base");
+    let main = repo.current_branch().unwrap().unwrap();
+
+    repo.create_local_branch(&BranchName::from_str("feature").unwrap())
+        .unwrap();
+    let feature_tip = commit_file(&repo, dir.as_ref(), "feature", "feature");
+    repo.switch_branch(&BranchName::from_str(&main).unwrap())
+        .unwrap();
+
+    assert_eq!(
+        MergeOutcome::UpToDate,
+        repo.merge(&MergeOptions::new(&main)).unwrap()
+    );
+    assert!(matches!(
+        repo.merge(&MergeOptions::new("no-such-branch")),
+        Err(GitError::GitError { .. })
+    ));
+
+    repo.create_local_branch(&BranchName::from_str("squashed").unwrap())
+        .unwrap();
+    assert_eq!(
+        MergeOutcome::Squashed,
+        repo.merge(&MergeOptions::new("feature").squash()).unwrap()
+    );
+    repo.commit(&CommitBuilder::new("Squashed feature"))
+        .unwrap();
+    repo.switch_branch(&BranchName::from_str(&main).unwrap())
+        .unwrap();
+
+    repo.create_local_branch(&BranchName::from_str("merged").unwrap())
+        .unwrap();
+    match repo
+        .merge(
+            &MergeOptions::new("feature")
+                .fast_forward(FastForward::Never)
+                .message("Merge feature"),
+        )
+        .unwrap()
+    {
+        MergeOutcome::Merged { commit } => {
+            let log = repo.log(&LogOptions::new().max_count(1)).unwrap();
+            assert_eq!(commit, log[0].hash);
+            assert_eq!("Merge feature", log[0].subject);
+            assert!(log[0].is_merge());
+        }
+        other => panic!("expected a merge commit, got {:?}", other),
+    }
+    repo.switch_branch(&BranchName::from_str(&main).unwrap())
+        .unwrap();
+
+    assert_eq!(
+        MergeOutcome::FastForwarded {
+            commit: feature_tip.clone()
+        },
+        repo.merge(&MergeOptions::new("feature").fast_forward(FastForward::Only))
+            .unwrap()
+    );
+
+    commit_file(&repo, dir.as_ref(), "base", "ours");
+    repo.switch_branch(&BranchName::from_str("feature").unwrap())
+        .unwrap();
+    commit_file(&repo, dir.as_ref(), "base", "theirs");
+    repo.switch_branch(&BranchName::from_str(&main).unwrap())
+        .unwrap();
+
+    match repo.merge(&MergeOptions::new("feature")).unwrap() {
+        MergeOutcome::Conflicted { conflicts } => {
+            assert_eq!(1, conflicts.len());
+            assert_eq!("base", conflicts[0].path);
+            assert!(conflicts[0].base.is_some());
+            assert_ne!(conflicts[0].ours, conflicts[0].theirs);
+        }
+        other => panic!("expected conflicts, got {:?}", other),
+    }
+    repo.merge_abort().unwrap();
+    assert!(repo.status().unwrap().is_clean());
+
+    assert!(matches!(
+        repo.merge(&MergeOptions::new("feature")).unwrap(),
+        MergeOutcome::Conflicted { .. }
+    ));
+    fs::write(dir.as_ref().join("base"), "resolved").unwrap();
+    repo.add(vec!["base"]).unwrap();
+    let merge_commit = repo.merge_continue().unwrap();
+    let log = repo.log(&LogOptions::new().max_count(1)).unwrap();
+    assert_eq!(merge_commit, log[0].hash);
+    assert!(log[0].is_merge());
+
+    assert_eq!(
+        MergeOutcome::UpToDate,
+        repo.merge(
+            &MergeOptions::new("feature")
+                .strategy("ort")
+                .strategy_option("theirs")
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_rebase() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    commit_file(&repo, dir.as_ref(), "base", "base");
+    let main = repo.current_branch().unwrap().unwrap();
+
+    repo.create_local_branch(&BranchName::from_str("feature").unwrap())
+        .unwrap();
+    commit_file(&repo, dir.as_ref(), "a", "a");
+    commit_file(&repo, dir.as_ref(), "b", "b");
+    fs::write(dir.as_ref().join("a"), "fixed").unwrap();
+    repo.add(vec!["a"]).unwrap();
+    repo.commit(&CommitBuilder::new("fixup! Write a")).unwrap();
+    assert_eq!(None, repo.rebase_state().unwrap());
+
+    let outcome = repo
+        .rebase(
+            &RebaseOptions::new()
+                .upstream(&main)
+                .autosquash()
+                .exec("true"),
+        )
+        .unwrap();
+    let log = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!(
+        RebaseOutcome::Completed {
+            commit: log[0].hash.clone()
+        },
+        outcome
+    );
+    let subjects: Vec<&str> = log.iter().map(|c| c.subject.as_str()).collect();
+    assert_eq!(vec!["Write b", "Write a", "Write base"], subjects);
+    assert_eq!("fixed", fs::read_to_string(dir.as_ref().join("a")).unwrap());
+
+    repo.switch_branch(&BranchName::from_str(&main).unwrap())
+        .unwrap();
+    let onto = commit_file(&repo, dir.as_ref(), "a", "conflicting");
+    repo.switch_branch(&BranchName::from_str("feature").unwrap())
+        .unwrap();
+    let before = repo.get_hash(false).unwrap();
+
+    match repo.rebase(&RebaseOptions::new().upstream(&main)).unwrap() {
+        RebaseOutcome::Stopped { state, conflicts } => {
+            assert_eq!((1, 2), (state.current, state.total));
+            assert_eq!(Some(String::from("feature")), state.branch);
+            assert_eq!(onto, state.onto);
+            assert_eq!(log[1].hash, state.commit.unwrap());
+            assert_eq!(1, conflicts.len());
+            assert_eq!("a", conflicts[0].path);
+        }
+        other => panic!("expected the rebase to stop, got {:?}", other),
+    }
+    assert!(repo.rebase_state().unwrap().is_some());
+    repo.rebase_abort().unwrap();
+    assert_eq!(None, repo.rebase_state().unwrap());
+    assert_eq!(before, repo.get_hash(false).unwrap());
+
+    assert!(matches!(
+        repo.rebase(&RebaseOptions::new().upstream(&main)).unwrap(),
+        RebaseOutcome::Stopped { .. }
+    ));
+    assert!(matches!(
+        repo.rebase_skip().unwrap(),
+        RebaseOutcome::Completed { .. }
+    ));
+    let log = repo.log(&LogOptions::new()).unwrap();
+    assert_eq!(
+        vec!["Write b", "Write a"],
+        log.iter()
+            .take(2)
+            .map(|c| c.subject.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(onto, log[1].hash);
+
+    let picked = repo.get_hash(false).unwrap();
+    match repo
+        .rebase(&RebaseOptions::new().upstream("HEAD~1").exec("false"))
+        .unwrap()
+    {
+        RebaseOutcome::Stopped { state, conflicts } => {
+            assert_eq!((2, 2), (state.current, state.total));
+            assert_eq!(Some(picked), state.commit);
+            assert!(conflicts.is_empty());
+        }
+        other => panic!("expected the rebase to stop, got {:?}", other),
+    }
+    assert!(matches!(
+        repo.rebase_continue().unwrap(),
+        RebaseOutcome::Completed { .. }
+    ));
+    assert!(matches!(
+        repo.rebase_continue(),
+        Err(GitError::GitError { .. })
+    ));
+}
+
+#[test]
+fn test_stash() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    commit_file(&repo, dir.as_ref(), "staged", "base");
+    commit_file(&repo, dir.as_ref(), "unstaged", "base");
+    let branch = repo.current_branch().unwrap();
+
+    assert_eq!(None, repo.stash_push(&StashOptions::new()).unwrap());
+    assert!(repo.stash_list().unwrap().is_empty());
+
+    fs::write(dir.as_ref().join("staged"), "staged").unwrap();
+    repo.add(vec!["staged"]).unwrap();
+    fs::write(dir.as_ref().join("unstaged"), "unstaged").unwrap();
+    fs::write(dir.as_ref().join("untracked"), "untracked").unwrap();
+
+    let hash = repo
+        .stash_push(
+            &StashOptions::new()
+                .message("Before linting")
+                .keep_index()
+                .include_untracked(),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        "staged",
+        fs::read_to_string(dir.as_ref().join("staged")).unwrap()
+    );
+    assert_eq!(
+        "base",
+        fs::read_to_string(dir.as_ref().join("unstaged")).unwrap()
+    );
+    assert!(!dir.as_ref().join("untracked").exists());
+
+    let stashes = repo.stash_list().unwrap();
+    assert_eq!(1, stashes.len());
+    assert_eq!(0, stashes[0].index);
+    assert_eq!(hash, stashes[0].hash);
+    assert_eq!(branch, stashes[0].branch);
+    assert_eq!("Before linting", stashes[0].message);
+
+    let diff = repo.stash_show(0).unwrap();
+    let paths: Vec<&str> = diff
+        .files
+        .iter()
+        .filter_map(|f| f.new_path.as_deref())
+        .collect();
+    assert_eq!(vec!["staged", "unstaged"], paths);
+
+    repo.cmd(["checkout", "--", "."]).unwrap();
+    assert_eq!(StashApplyOutcome::Applied, repo.stash_pop(0).unwrap());
+    assert!(repo.stash_list().unwrap().is_empty());
+    assert_eq!(
+        "unstaged",
+        fs::read_to_string(dir.as_ref().join("unstaged")).unwrap()
+    );
+    assert!(dir.as_ref().join("untracked").exists());
+
+    repo.stash_push(&StashOptions::new().path("unstaged"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        "staged",
+        fs::read_to_string(dir.as_ref().join("staged")).unwrap()
+    );
+    commit_file(&repo, dir.as_ref(), "unstaged", "conflicting");
+    match repo.stash_pop(0).unwrap() {
+        StashApplyOutcome::Conflicted { conflicts } => {
+            assert_eq!(1, conflicts.len());
+            assert_eq!("unstaged", conflicts[0].path);
+        }
+        other => panic!("expected conflicts, got {:?}", other),
+    }
+    assert_eq!(1, repo.stash_list().unwrap().len());
+
+    repo.stash_drop(0).unwrap();
+    assert!(repo.stash_list().unwrap().is_empty());
+    assert!(matches!(repo.stash_drop(0), Err(GitError::GitError { .. })));
+}
+
+#[test]
+fn test_worktrees() {
+    let dir = tempfile::tempdir().unwrap();
+    let main_dir = dir.as_ref().join("main");
+    fs::create_dir(&main_dir).unwrap();
+    let repo = Repository::init(&main_dir).unwrap();
+    let head = commit_file(&repo, &main_dir, "file", "content");
+    let main = repo.current_branch().unwrap();
+
+    let ci = repo
+        .add_worktree(
+            "../ci",
+            &WorktreeOptions::new()
+                .new_branch(&BranchName::from_str("ci").unwrap())
+                .branch(&head),
+        )
+        .unwrap();
+    assert_eq!(Some(String::from("ci")), ci.current_branch().unwrap());
+    let ci_commit = commit_file(&ci, &dir.as_ref().join("ci"), "file", "changed");
+    assert_eq!(ci_commit, repo.cmd_out(["rev-parse", "ci"]).unwrap()[0]);
+
+    let detached = repo
+        .add_worktree(
+            dir.as_ref().join("detached"),
+            &WorktreeOptions::new().branch("ci").detach(),
+        )
+        .unwrap();
+    assert_eq!(None, detached.current_branch().unwrap());
+    repo.lock_worktree(dir.as_ref().join("detached"), Some("ci job"))
+        .unwrap();
+
+    let worktrees = repo.list_worktrees().unwrap();
+    assert_eq!(3, worktrees.len());
+    assert_eq!(main, worktrees[0].branch);
+    assert_eq!(Some(head), worktrees[0].head);
+    assert_eq!(Some(String::from("ci")), worktrees[1].branch);
+    assert_eq!(
+        fs::canonicalize(dir.as_ref().join("ci")).unwrap(),
+        worktrees[1].path
+    );
+    assert!(worktrees[2].is_detached);
+    assert!(worktrees[2].locked);
+    assert_eq!(Some(String::from("ci job")), worktrees[2].lock_reason);
+
+    assert!(matches!(
+        repo.remove_worktree(dir.as_ref().join("detached"), false),
+        Err(GitError::GitError { .. })
+    ));
+    repo.unlock_worktree(dir.as_ref().join("detached")).unwrap();
+    repo.remove_worktree(dir.as_ref().join("detached"), false)
+        .unwrap();
+
+    fs::remove_dir_all(dir.as_ref().join("ci")).unwrap();
+    assert!(repo.list_worktrees().unwrap()[1].prunable);
+    repo.prune_worktrees().unwrap();
+    assert_eq!(1, repo.list_worktrees().unwrap().len());
+}
+
+#[test]
+fn test_submodules() {
+    let lib_dir = tempfile::tempdir().unwrap();
+    let lib = Repository::init(&lib_dir).unwrap();
+    let first = commit_file(&lib, lib_dir.as_ref(), "lib", "1");
+    let second = commit_file(&lib, lib_dir.as_ref(), "lib", "2");
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::builder(&dir)
+        .config("protocol.file.allow", "always")
+        .init()
+        .unwrap();
+    commit_file(&repo, dir.as_ref(), "file", "content");
+    assert!(repo.submodule_status().unwrap().is_empty());
+
+    let url = lib_dir.as_ref().to_str().unwrap();
+    let submodule = repo.submodule_add(url, "vendor/lib", None).unwrap();
+    assert_eq!(second, submodule.get_hash(false).unwrap());
+    repo.commit(&CommitBuilder::new("Add lib")).unwrap();
+
+    let status = repo.submodule_status().unwrap();
+    assert_eq!(1, status.len());
+    assert_eq!("vendor/lib", status[0].name);
+    assert_eq!("vendor/lib", status[0].path);
+    assert_eq!(Some(url.to_owned()), status[0].url);
+    assert_eq!(Some(second.clone()), status[0].recorded_commit);
+    assert_eq!(Some(second.clone()), status[0].checked_out_commit);
+    assert!(status[0].initialised);
+    assert!(!status[0].modified);
+
+    repo.submodule("vendor/lib")
+        .cmd(["checkout", "--detach", &first])
+        .unwrap();
+    let status = repo.submodule_status().unwrap();
+    assert_eq!(Some(first), status[0].checked_out_commit);
+    assert!(status[0].modified);
+
+    repo.submodule_update(&SubmoduleUpdateOptions::new())
+        .unwrap();
+    assert!(!repo.submodule_status().unwrap()[0].modified);
+    repo.submodule_foreach("test -f lib", true).unwrap();
+    assert!(matches!(
+        repo.submodule_foreach("false", false),
+        Err(GitError::GitError { .. })
+    ));
+
+    repo.cmd(["submodule", "deinit", "--all"]).unwrap();
+    let status = repo.submodule_status().unwrap();
+    assert!(!status[0].initialised);
+    assert_eq!(None, status[0].checked_out_commit);
+    assert_eq!(Some(second.clone()), status[0].recorded_commit);
+
+    repo.submodule_update(
+        &SubmoduleUpdateOptions::new()
+            .init()
+            .recursive()
+            .path("vendor/lib"),
+    )
+    .unwrap();
+    assert!(repo.submodule_status().unwrap()[0].initialised);
+    repo.submodule_sync(true).unwrap();
+}
+
+#[test]
+fn test_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let global = home.as_ref().join("gitconfig");
+    let repo = Repository::builder(&dir)
+        .env("GIT_CONFIG_GLOBAL", &global)
+        .env("HOME", home.as_ref())
+        .init()
+        .unwrap();
+
+    assert_eq!(None, repo.config_get("user.name", None).unwrap());
+    assert!(repo
+        .config_get_all("credential.helper", None)
+        .unwrap()
+        .is_empty());
+
+    repo.config_set("user.name", "Global User", &ConfigScope::Global)
+        .unwrap();
+    repo.config_set("user.name", "Local User", &ConfigScope::Local)
+        .unwrap();
+    assert_eq!(
+        Some(String::from("Local User")),
+        repo.config_get("user.name", None).unwrap()
+    );
+    assert_eq!(
+        Some(String::from("Global User")),
+        repo.config_get("user.name", Some(&ConfigScope::Global))
+            .unwrap()
+    );
+    assert!(fs::read_to_string(&global).unwrap().contains("Global User"));
+
+    repo.config_add("credential.helper", "", &ConfigScope::Local)
+        .unwrap();
+    repo.config_add(
+        "credential.helper",
+        "store --file=-creds",
+        &ConfigScope::Local,
+    )
+    .unwrap();
+    assert_eq!(
+        vec!["", "store --file=-creds"],
+        repo.config_get_all("credential.helper", None).unwrap()
+    );
+    repo.config_set("credential.helper", "cache", &ConfigScope::Local)
+        .unwrap();
+    assert_eq!(
+        vec!["cache"],
+        repo.config_get_all("credential.helper", None).unwrap()
+    );
+
+    repo.config_set("x.flag", "yes", &ConfigScope::Local)
+        .unwrap();
+    repo.config_set("x.size", "2k", &ConfigScope::Local)
+        .unwrap();
+    repo.config_set("core.hooksPath", "~/hooks", &ConfigScope::Local)
+        .unwrap();
+    assert_eq!(Some(true), repo.config_get_bool("x.flag", None).unwrap());
+    assert_eq!(Some(2048), repo.config_get_int("x.size", None).unwrap());
+    assert_eq!(
+        Some(home.as_ref().join("hooks")),
+        repo.config_get_path("core.hooksPath", None).unwrap()
+    );
+    assert!(repo.config_get_int("x.flag", None).is_err());
+
+    let file = ConfigScope::File(dir.as_ref().join("custom.cfg"));
+    repo.config_set("custom.key", "value", &file).unwrap();
+    assert_eq!(
+        Some(String::from("value")),
+        repo.config_get("custom.key", Some(&file)).unwrap()
+    );
+    assert_eq!(None, repo.config_get("custom.key", None).unwrap());
+
+    let entries = repo.config_list(None).unwrap();
+    let name = entries
+        .iter()
+        .find(|e| e.key == "user.name" && e.scope == Some(ConfigScope::Global))
+        .unwrap();
+    assert_eq!(Some(String::from("Global User")), name.value);
+    assert_eq!(format!("file:{}", global.display()), name.origin);
+    let local = repo.config_list(Some(&ConfigScope::Local)).unwrap();
+    assert!(local.iter().all(|e| e.scope == Some(ConfigScope::Local)));
+    assert!(local.iter().any(|e| e.key == "core.hookspath"));
+
+    repo.config_unset("user.name", &ConfigScope::Local).unwrap();
+    repo.config_unset("user.name", &ConfigScope::Local).unwrap();
+    assert_eq!(
+        Some(String::from("Global User")),
+        repo.config_get("user.name", None).unwrap()
+    );
+}
+
+#[test]
+fn test_discover_and_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = fs::canonicalize(&dir).unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    commit_file(&repo, dir.as_ref(), "file", "content");
+    fs::create_dir_all(dir.as_ref().join("sub/dir")).unwrap();
+
+    let discovered = Repository::discover(dir.as_ref().join("sub/dir")).unwrap();
+    assert_eq!(Some(root.clone()), discovered.workdir().unwrap());
+    assert_eq!(root.join(".git"), discovered.git_dir().unwrap());
+    assert!(!discovered.is_bare());
+    assert!(!discovered.is_shallow().unwrap());
+    assert!(!discovered.is_worktree().unwrap());
+    assert_eq!(ObjectFormat::Sha1, discovered.object_format().unwrap());
+    assert_eq!(vec!["file"], discovered.cmd_out(["ls-files"]).unwrap());
+
+    assert!(Repository::open(&dir).is_ok());
+    assert!(matches!(
+        Repository::open(dir.as_ref().join("sub")),
+        Err(GitError::NotARepository { .. })
+    ));
+    let empty = tempfile::tempdir().unwrap();
+    assert!(matches!(
+        Repository::discover(&empty),
+        Err(GitError::NotARepository { .. })
+    ));
+
+    let bare_dir = tempfile::tempdir().unwrap();
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--bare", "--depth=1"])
+        .arg(format!("file://{}", root.display()))
+        .arg(bare_dir.as_ref())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let bare = Repository::open(&bare_dir).unwrap();
+    assert!(bare.is_bare());
+    assert!(bare.is_shallow().unwrap());
+    assert_eq!(None, bare.workdir().unwrap());
+
+    let linked_dir = tempfile::tempdir().unwrap();
+    let worktree = repo
+        .add_worktree(
+            linked_dir.as_ref().join("linked"),
+            &WorktreeOptions::new().detach(),
+        )
+        .unwrap();
+    assert!(worktree.is_worktree().unwrap());
+    let linked = Repository::discover(linked_dir.as_ref().join("linked")).unwrap();
+    assert!(linked.is_worktree().unwrap());
+}
+
+#[test]
+fn test_pull() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    let upstream = Repository::init(&upstream_dir).unwrap();
+    let first = commit_file(&upstream, upstream_dir.as_ref(), "upstream", "1");
+    let main = upstream.current_branch().unwrap().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        PullOutcome::FastForwarded {
+            from: None,
+            to: first.clone()
+        },
+        repo.pull(&PullOptions::new().branch(&main)).unwrap()
+    );
+    repo.cmd(["branch", "--set-upstream-to", &format!("origin/{}", main)])
+        .unwrap();
+    assert_eq!(
+        PullOutcome::UpToDate,
+        repo.pull(&PullOptions::new()).unwrap()
+    );
+
+    let second = commit_file(&upstream, upstream_dir.as_ref(), "upstream", "2");
+    assert_eq!(
+        PullOutcome::FastForwarded {
+            from: Some(first),
+            to: second
+        },
+        repo.pull(&PullOptions::new().mode(PullMode::FastForwardOnly).no_tags())
+            .unwrap()
+    );
+
+    commit_file(&upstream, upstream_dir.as_ref(), "upstream", "3");
+    commit_file(&repo, dir.as_ref(), "local", "1");
+    assert!(matches!(
+        repo.pull(&PullOptions::new().mode(PullMode::FastForwardOnly)),
+        Err(GitError::GitError { .. })
+    ));
+    match repo.pull(&PullOptions::new()).unwrap() {
+        PullOutcome::Merged { commit } => {
+            let log = repo.log(&LogOptions::new().max_count(1)).unwrap();
+            assert_eq!(commit, log[0].hash);
+            assert!(log[0].is_merge());
+        }
+        other => panic!("expected a merge, got {:?}", other),
+    }
+
+    commit_file(&upstream, upstream_dir.as_ref(), "upstream", "4");
+    commit_file(&repo, dir.as_ref(), "local", "2");
+    fs::write(dir.as_ref().join("local"), "uncommitted").unwrap();
+    match repo
+        .pull(&PullOptions::new().mode(PullMode::Rebase).autostash())
+        .unwrap()
+    {
+        PullOutcome::Rebased { commit } => assert_eq!(commit, repo.get_hash(false).unwrap()),
+        other => panic!("expected a rebase, got {:?}", other),
+    }
+    assert_eq!(
+        "uncommitted",
+        fs::read_to_string(dir.as_ref().join("local")).unwrap()
+    );
+    repo.cmd(["checkout", "--", "local"]).unwrap();
+
+    commit_file(&upstream, upstream_dir.as_ref(), "shared", "theirs");
+    commit_file(&repo, dir.as_ref(), "shared", "ours");
+    match repo
+        .pull(&PullOptions::new().mode(PullMode::Rebase))
+        .unwrap()
+    {
+        PullOutcome::Conflicted { conflicts } => {
+            assert_eq!(1, conflicts.len());
+            assert_eq!("shared", conflicts[0].path);
+        }
+        other => panic!("expected conflicts, got {:?}", other),
+    }
+    assert!(repo.rebase_state().unwrap().is_some());
+    repo.rebase_abort().unwrap();
+}
+
+#[test]
+fn test_remotes() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    let upstream = Repository::init(&upstream_dir).unwrap();
+    commit_file(&upstream, upstream_dir.as_ref(), "upstream", "1");
+    let main = upstream.current_branch().unwrap().unwrap();
+    upstream.cmd(["branch", "feature"]).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    assert!(repo.remotes().unwrap().is_empty());
+
+    let url = GitUrl::from_str("https://example.com/repo.git").unwrap();
+    let push_url = GitUrl::from_str("git@example.com:me/repo.git").unwrap();
+    repo.add_remote("origin", &url).unwrap();
+    repo.set_remote_url("origin", &push_url, true).unwrap();
+    repo.rename_remote("origin", "upstream").unwrap();
+
+    let remotes = repo.remotes().unwrap();
+    assert_eq!(1, remotes.len());
+    assert_eq!("upstream", remotes[0].name);
+    assert_eq!(Some(url.to_string()), remotes[0].fetch_url);
+    assert_eq!(vec![push_url.to_string()], remotes[0].push_urls);
+    assert_eq!(
+        vec!["+refs/heads/*:refs/remotes/upstream/*"],
+        remotes[0].fetch_refspecs
+    );
+
+    repo.remove_remote("upstream").unwrap();
+    assert!(repo.remotes().unwrap().is_empty());
+
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+    repo.fetch_remote("origin").unwrap();
+    repo.remote_set_head("origin", &RemoteHead::Branch(main.clone()))
+        .unwrap();
+    assert_eq!(
+        format!("origin/{}", main),
+        repo.cmd_out(["rev-parse", "--abbrev-ref", "origin/HEAD"])
+            .unwrap()[0]
+    );
+
+    upstream.cmd(["branch", "-D", "feature"]).unwrap();
+    assert_eq!(
+        vec!["origin/feature"],
+        repo.prune_remote("origin", true).unwrap()
+    );
+    assert_eq!(
+        vec!["origin/feature"],
+        repo.prune_remote("origin", false).unwrap()
+    );
+    assert!(repo.prune_remote("origin", false).unwrap().is_empty());
+}
+
+#[test]
+fn test_fetch() {
+    let old = "1".repeat(40);
+    let new = "2".repeat(40);
+    let runner = Arc::new(ScriptedRunner::new());
+    runner
+        .push_success(&format!("  {} {} refs/remotes/origin/main\n", old, new))
+        .push(GitOutput {
+            exit_code: Some(1),
+            stdout: format!("! {} {} refs/heads/main\n", new, old).into_bytes(),
+            stderr: b"error: some local refs could not be updated\n".to_vec(),
+            interrupted: None,
+        })
+        .push_failure(128, "fatal: couldn't find remote ref nope\n");
+
+    let repo = Repository::with_runner("/some/repo", runner.clone());
+
+    let updates = repo
+        .fetch(&FetchOptions::new().remote("origin").prune())
+        .unwrap();
+    assert_eq!(1, updates.len());
+    assert_eq!("refs/remotes/origin/main", updates[0].reference);
+    assert_eq!(Some(old.clone()), updates[0].old);
+    assert_eq!(Some(new.clone()), updates[0].new);
+    assert_eq!(UpdateFlag::FastForward, updates[0].flag);
+
+    let updates = repo
+        .fetch(&FetchOptions::new().refspec("main:main"))
+        .unwrap();
+    assert_eq!(UpdateFlag::Rejected, updates[0].flag);
+
+    assert!(matches!(
+        repo.fetch(&FetchOptions::new().refspec("nope")),
+        Err(GitError::RefNotFound { .. })
+    ));
+
+    let invocations = runner.invocations();
+    assert_eq!(
+        vec![
+            "fetch",
+            "--porcelain",
+            "--prune",
+            "--end-of-options",
+            "origin"
+        ],
+        invocations[0].args_lossy()
+    );
+    assert_eq!(
+        vec![
+            "fetch",
+            "--porcelain",
+            "--end-of-options",
+            "origin",
+            "main:main"
+        ],
+        invocations[1].args_lossy()
+    );
+}
+
+#[test]
+fn test_push_with() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .args(["init", "--bare", "-q"])
+        .arg(upstream_dir.as_ref())
+        .status()
+        .unwrap();
+    Repository::new(&upstream_dir)
+        .config_set("receive.advertisePushOptions", "true", &ConfigScope::Local)
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    repo.cmd([
+        "remote",
+        "add",
+        "origin",
+        upstream_dir.as_ref().to_str().unwrap(),
+    ])
+    .unwrap();
+    let first = commit_file(&repo, dir.as_ref(), "file", "1");
+    let main = repo.current_branch().unwrap().unwrap();
+    repo.cmd(["branch", "feature"]).unwrap();
+
+    let results = repo
+        .push_with(
+            &PushOptions::new()
+                .refspec(&main)
+                .refspec("feature")
+                .push_option("ci.skip"),
+        )
+        .unwrap();
+    assert_eq!(2, results.len());
+    assert_eq!(format!("refs/heads/{}", main), results[0].reference);
+    assert_eq!(PushStatus::New, results[0].status);
+    assert_eq!(
+        PushStatus::UpToDate,
+        repo.push_with(&PushOptions::new().refspec(&main)).unwrap()[0].status
+    );
+
+    repo.cmd(["commit", "--amend", "-m", "rewritten"]).unwrap();
+    let rejected = repo.push_with(&PushOptions::new().refspec(&main)).unwrap();
+    assert!(matches!(rejected[0].status, PushStatus::Rejected { .. }));
+
+    let lease = format!("refs/heads/{}", main);
+    let stale = repo
+        .push_with(
+            &PushOptions::new()
+                .refspec(&main)
+                .force_with_lease(&lease, Some(&"0".repeat(40))),
+        )
+        .unwrap();
+    assert_eq!(
+        PushStatus::Rejected {
+            reason: String::from("stale info")
+        },
+        stale[0].status
+    );
+    let forced = repo
+        .push_with(
+            &PushOptions::new()
+                .refspec(&main)
+                .force_with_lease(&lease, Some(&first))
+                .dry_run(),
+        )
+        .unwrap();
+    assert_eq!(PushStatus::Forced, forced[0].status);
+
+    let deleted = repo
+        .push_with(&PushOptions::new().delete().refspec("feature"))
+        .unwrap();
+    assert_eq!(None, deleted[0].source);
+    assert_eq!(PushStatus::Deleted, deleted[0].status);
+
+    repo.cmd(["remote", "set-url", "origin", "/nonexistent/repo.git"])
+        .unwrap();
+    assert!(repo.push_with(&PushOptions::new().refspec(&main)).is_err());
+}
+
+#[test]
+#[ignore = "requires git 2.41 or later for `fetch --porcelain`, run with --ignored"]
+fn test_fetch_from_local_remote() {
+    let upstream_dir = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .args(["init", "--bare", "-q"])
+        .arg(upstream_dir.as_ref())
+        .status()
+        .unwrap();
+    let upstream_url = upstream_dir.as_ref().to_str().unwrap();
+
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = Repository::init(&source_dir).unwrap();
+    source
+        .cmd(["remote", "add", "origin", upstream_url])
+        .unwrap();
+    let first = commit_file(&source, source_dir.as_ref(), "file", "1");
+    let main = source.current_branch().unwrap().unwrap();
+    source.cmd(["branch", "feature"]).unwrap();
+    source
+        .push_with(&PushOptions::new().refspec(&main).refspec("feature"))
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    repo.cmd(["remote", "add", "origin", upstream_url]).unwrap();
+
+    let main_ref = format!("refs/remotes/origin/{}", main);
+    let mut updates = repo.fetch(&FetchOptions::new().remote("origin")).unwrap();
+    updates.sort_by(|a, b| a.reference.cmp(&b.reference));
+    assert_eq!(2, updates.len());
+    assert_eq!("refs/remotes/origin/feature", updates[0].reference);
+    assert_eq!(main_ref, updates[1].reference);
+    assert_eq!(None, updates[1].old);
+    assert_eq!(Some(first.clone()), updates[1].new);
+    assert_eq!(UpdateFlag::New, updates[1].flag);
+
+    let second = commit_file(&source, source_dir.as_ref(), "file", "2");
+    source
+        .push_with(&PushOptions::new().refspec(&main))
+        .unwrap();
+    source
+        .push_with(&PushOptions::new().delete().refspec("feature"))
+        .unwrap();
+
+    let mut updates = repo.fetch(&FetchOptions::new().prune()).unwrap();
+    updates.sort_by(|a, b| a.reference.cmp(&b.reference));
+    assert_eq!(2, updates.len());
+    assert_eq!("refs/remotes/origin/feature", updates[0].reference);
+    assert_eq!(UpdateFlag::Deleted, updates[0].flag);
+    assert_eq!(main_ref, updates[1].reference);
+    assert_eq!(Some(first), updates[1].old);
+    assert_eq!(Some(second), updates[1].new);
+    assert_eq!(UpdateFlag::FastForward, updates[1].flag);
+
+    assert!(matches!(
+        repo.fetch(&FetchOptions::new().refspec("nope")),
+        Err(GitError::RefNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_reset_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    let path = dir.as_ref().join("file");
+    let first = commit_file(&repo, dir.as_ref(), "file", "1");
+    let second = commit_file(&repo, dir.as_ref(), "file", "2");
+
+    repo.reset(ResetMode::Soft, &first).unwrap();
+    assert_eq!(first, repo.get_hash(false).unwrap());
+    assert_eq!(vec!["file"], repo.list_modified().unwrap());
+    assert_eq!("2", fs::read_to_string(&path).unwrap());
+
+    repo.restore(vec!["file"], None, true, false).unwrap();
+    assert_eq!("2", fs::read_to_string(&path).unwrap());
+    repo.restore(vec!["file"], None, false, false).unwrap();
+    assert_eq!("1", fs::read_to_string(&path).unwrap());
+    repo.restore(vec!["file"], Some(&second), false, true)
+        .unwrap();
+    assert_eq!("2", fs::read_to_string(&path).unwrap());
+
+    repo.reset(ResetMode::Hard, &second).unwrap();
+    repo.checkout_paths(Some(&first), vec!["file"]).unwrap();
+    assert_eq!("1", fs::read_to_string(&path).unwrap());
+    repo.checkout_paths(None, vec!["file"]).unwrap();
+    assert_eq!("1", fs::read_to_string(&path).unwrap());
+    assert!(repo.checkout_paths(None, vec!["missing"]).is_err());
+
+    fs::write(dir.as_ref().join("untracked"), "new").unwrap();
+    fs::create_dir(dir.as_ref().join("build")).unwrap();
+    fs::write(dir.as_ref().join("build").join("output"), "new").unwrap();
+    fs::write(
+        dir.as_ref().join(".git").join("info").join("exclude"),
+        "ignored\n",
+    )
+    .unwrap();
+    fs::write(dir.as_ref().join("ignored"), "kept").unwrap();
+    repo.discard_all_changes().unwrap();
+    assert_eq!(second, repo.get_hash(false).unwrap());
+    assert_eq!("2", fs::read_to_string(&path).unwrap());
+    assert!(repo.list_modified().unwrap().is_empty());
+    assert!(repo.list_untracked().unwrap().is_empty());
+    assert!(!dir.as_ref().join("build").exists());
+    assert!(dir.as_ref().join("ignored").exists());
+}
+
+#[test]
+fn test_discard_all_changes_without_commits() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(&dir).unwrap();
+    fs::write(dir.as_ref().join("staged"), "new").unwrap();
+    repo.add(vec!["staged"]).unwrap();
+    fs::write(dir.as_ref().join("untracked"), "new").unwrap();
+    Command::new("git")
+        .args(["init", "-q", "nested"])
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+
+    repo.discard_all_changes().unwrap();
+    assert!(!dir.as_ref().join("staged").exists());
+    assert!(!dir.as_ref().join("untracked").exists());
+    assert!(dir.as_ref().join("nested").join(".git").exists());
+}